@@ -0,0 +1,300 @@
+use std::{hash::RandomState, hint::black_box};
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use grahh_db::{Database, Directionality, Key};
+use rkyv::string::ArchivedString;
+
+const NODES: usize = 10_000;
+const EDGES: usize = 50_000;
+const LARGE: usize = 100_000;
+
+/// xorshift so fixtures are the same on every run without pulling in `rand`
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self, below: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % below as u64) as usize
+    }
+}
+
+fn fill(db: &mut Database, nodes: usize) -> Vec<Key> {
+    (0..nodes)
+        .map(|i| db.create(&format!("node {i}")))
+        .collect()
+}
+
+fn wire(db: &mut Database, keys: &[Key], edges: usize) {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..edges {
+        let first = keys[rng.next(keys.len())];
+        let second = keys[rng.next(keys.len())];
+        if first != second {
+            db.connect(first, "friend".to_owned(), second, "friend".to_owned());
+        }
+    }
+}
+
+fn graph(nodes: usize, edges: usize) -> (Database, Vec<Key>) {
+    let mut db = Database::in_memory();
+    let keys = fill(&mut db, nodes);
+    wire(&mut db, &keys, edges);
+    (db, keys)
+}
+
+fn create(c: &mut Criterion) {
+    let values: Vec<String> = (0..NODES).map(|i| format!("node {i}")).collect();
+    let mut group = c.benchmark_group("create");
+    group.bench_function("create", |b| {
+        b.iter_batched(
+            Database::in_memory,
+            |mut db| {
+                values
+                    .iter()
+                    .map(|value| db.create(value))
+                    .collect::<Vec<_>>()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("create_many", |b| {
+        b.iter_batched(
+            Database::in_memory,
+            |mut db| db.create_many(&values),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn connect(c: &mut Criterion) {
+    c.bench_function("connect", |b| {
+        b.iter_batched(
+            || {
+                let mut db = Database::in_memory();
+                let keys = fill(&mut db, NODES);
+                (db, keys)
+            },
+            |(mut db, keys)| wire(&mut db, &keys, EDGES),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn traverse(c: &mut Criterion) {
+    let (db, keys) = graph(NODES, EDGES);
+    c.bench_function("traverse 3 hops", |b| {
+        b.iter(|| {
+            db.query()
+                .starting_at(keys[0])
+                .follow("friend")
+                .follow("friend")
+                .follow("friend")
+                .collect()
+        })
+    });
+}
+
+fn paths(c: &mut Criterion) {
+    let (mut db, keys) = graph(NODES, EDGES);
+    db.declare_kind("friend", Directionality::Undirected);
+    let (from, to) = (keys[0], keys[NODES - 1]);
+    let mut group = c.benchmark_group("paths");
+    group.bench_function("shortest_path", |b| {
+        b.iter(|| db.shortest_path(&from, &to, "friend"))
+    });
+    group.bench_function("shortest_path_bidirectional", |b| {
+        b.iter(|| db.shortest_path_bidirectional(&from, &to, "friend"))
+    });
+    group.finish();
+}
+
+fn read(c: &mut Criterion) {
+    let (db, keys) = graph(NODES, 0);
+    let mut group = c.benchmark_group("read");
+    group.bench_function("deserialize", |b| {
+        b.iter(|| {
+            for key in &keys {
+                black_box(db.get(key).unwrap().value().deserialize::<ArchivedString>());
+            }
+        })
+    });
+    group.bench_function("deserialize_cached", |b| {
+        b.iter(|| {
+            for key in &keys {
+                black_box(
+                    db.get(key)
+                        .unwrap()
+                        .value()
+                        .deserialize_cached::<ArchivedString>(),
+                );
+            }
+        })
+    });
+    group.finish();
+}
+
+fn persistence(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut db = Database::load(path.clone());
+    let keys = fill(&mut db, LARGE);
+    wire(&mut db, &keys, LARGE * 2);
+
+    let mut group = c.benchmark_group("persistence");
+    group.sample_size(10);
+    group.bench_function("save 100k", |b| b.iter(|| db.save()));
+    db.set_durable(true);
+    group.bench_function("save durable 100k", |b| b.iter(|| db.save()));
+    db.set_durable(false);
+    group.bench_function("load 100k", |b| b.iter(|| Database::load(path.clone())));
+    group.bench_function("verify 100k", |b| b.iter(|| Database::verify(&path)));
+    db.declare_kind("friend", Directionality::Directed);
+    group.bench_function("rebuild_indexes 100k", |b| b.iter(|| db.rebuild_indexes()));
+    group.finish();
+}
+
+fn hasher(c: &mut Criterion) {
+    let (db, keys) = graph(NODES, 0);
+    let mut sip: Database<RandomState> = Database::in_memory_with_hasher();
+    let sip_keys: Vec<Key> = (0..NODES)
+        .map(|i| sip.create(&format!("node {i}")))
+        .collect();
+    let mut group = c.benchmark_group("hasher");
+    group.bench_function("lookup fx", |b| {
+        b.iter(|| keys.iter().filter(|key| db.get(key).is_some()).count())
+    });
+    group.bench_function("lookup sip", |b| {
+        b.iter(|| sip_keys.iter().filter(|key| sip.get(key).is_some()).count())
+    });
+    group.finish();
+}
+
+/// removing a node everything else is connected to, with normal and with weak connections
+fn hub(c: &mut Criterion) {
+    let setup = |weak: bool| {
+        move || {
+            let mut db = Database::in_memory();
+            let keys = fill(&mut db, NODES);
+            let hub = db.create(&"hub".to_owned());
+            for key in keys {
+                if weak {
+                    db.connect_weak(key, "follows", hub);
+                } else {
+                    db.connect(key, "follows", hub, "follows");
+                }
+            }
+            (db, hub)
+        }
+    };
+    let mut group = c.benchmark_group("hub");
+    group.bench_function("remove 10k connections", |b| {
+        b.iter_batched(
+            setup(false),
+            // handing the database back keeps dropping it out of the timing
+            |(mut db, hub)| {
+                db.remove(hub);
+                db
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("remove 10k weak connections", |b| {
+        b.iter_batched(
+            setup(true),
+            |(mut db, hub)| {
+                db.remove(hub);
+                db
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+#[cfg(feature = "rayon")]
+fn parallel(c: &mut Criterion) {
+    let (db, _keys) = graph(NODES / 5, EDGES / 5);
+    let mut group = c.benchmark_group("parallel");
+    group.sample_size(10);
+    group.bench_function("diameter 2k", |b| b.iter(|| db.diameter("friend")));
+    group.bench_function("par_diameter 2k", |b| b.iter(|| db.par_diameter("friend")));
+    group.finish();
+}
+
+#[cfg(not(feature = "rayon"))]
+fn parallel(_c: &mut Criterion) {}
+
+#[cfg(feature = "zstd")]
+fn compression(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut group = c.benchmark_group("compression");
+    group.sample_size(10);
+    for level in [1, 3, 9, 19] {
+        let path = dir.path().join(format!("db {level}.grahh"));
+        let mut db = Database::load_compressed(path.clone(), level);
+        let keys = fill(&mut db, LARGE);
+        wire(&mut db, &keys, LARGE * 2);
+        group.bench_function(format!("save 100k level {level}"), |b| b.iter(|| db.save()));
+        println!(
+            "level {level}: {} bytes",
+            std::fs::metadata(&path).unwrap().len()
+        );
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "zstd"))]
+fn compression(_c: &mut Criterion) {}
+
+/// creating small values with compression off, with a threshold they stay under and with
+/// every value compressed
+#[cfg(feature = "zstd")]
+fn value_compression(c: &mut Criterion) {
+    let values: Vec<String> = (0..NODES).map(|i| format!("node {i}")).collect();
+    let mut group = c.benchmark_group("value_compression");
+    for (name, threshold) in [
+        ("off", None),
+        ("threshold 256", Some(256)),
+        ("threshold 0", Some(0)),
+    ] {
+        group.bench_function(format!("create small {name}"), |b| {
+            b.iter_batched(
+                || {
+                    let mut db = Database::in_memory();
+                    db.set_value_compression(threshold, grahh_db::DEFAULT_COMPRESSION_LEVEL);
+                    db
+                },
+                |mut db| {
+                    for value in &values {
+                        db.create(value);
+                    }
+                    db
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "zstd"))]
+fn value_compression(_c: &mut Criterion) {}
+
+criterion_group!(
+    benches,
+    create,
+    connect,
+    traverse,
+    paths,
+    read,
+    persistence,
+    hasher,
+    hub,
+    parallel,
+    compression,
+    value_compression
+);
+criterion_main!(benches);
@@ -0,0 +1,197 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+    hash::BuildHasher,
+};
+
+#[cfg(feature = "rand")]
+use rand::{Rng, RngExt};
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{Database, Directionality, Key, KeySet, Node};
+
+impl<S: BuildHasher> Database<S> {
+    /// folds every node into `init`, for aggregating without collecting `iter`
+    pub fn fold<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &Key, &Node) -> B,
+    {
+        self.iter().fold(init, |acc, (key, node)| f(acc, key, node))
+    }
+    /// `fold` across threads, each thread folds some of the nodes into its own `init()`
+    /// and the results are combined with `reduce`
+    #[cfg(feature = "rayon")]
+    pub fn par_fold<B, I, F, R>(&self, init: I, f: F, reduce: R) -> B
+    where
+        B: Send,
+        I: Fn() -> B + Sync + Send,
+        F: Fn(B, &Key, &Node) -> B + Sync + Send,
+        R: Fn(B, B) -> B + Sync + Send,
+        S: Sync,
+    {
+        self.inner
+            .par_iter()
+            .filter(|(_key, node)| !node.is_deleted())
+            .fold(&init, |acc, (key, node)| f(acc, key, node))
+            .reduce(&init, reduce)
+    }
+    /// up to `n` keys picked uniformly at random without replacement, reservoir sampled in
+    /// one pass over `iter`
+    #[cfg(feature = "rand")]
+    pub fn sample(&self, n: usize, rng: &mut impl Rng) -> Vec<Key> {
+        let mut reservoir: Vec<Key> = Vec::with_capacity(n.min(self.inner.len()));
+        for (i, (key, _node)) in self.iter().enumerate() {
+            if i < n {
+                reservoir.push(*key);
+            } else {
+                let j = rng.random_range(0..=i);
+                if j < n {
+                    reservoir[j] = *key;
+                }
+            }
+        }
+        reservoir
+    }
+    /// the `k` nodes with the highest `Node::total_degree`, highest first and ties in key
+    /// order. keeps a heap of `k` nodes instead of sorting all of them, O(n log k)
+    pub fn top_by_degree(&self, k: usize) -> Vec<(Key, usize)> {
+        if k == 0 {
+            return Vec::new();
+        }
+        // min heap of the best so far, the smallest degree and then the largest key is on top
+        let mut heap: BinaryHeap<Reverse<(usize, Reverse<Key>)>> = BinaryHeap::with_capacity(k + 1);
+        for (key, node) in self.iter() {
+            heap.push(Reverse((node.total_degree(), Reverse(*key))));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((degree, Reverse(key)))| (key, degree))
+            .collect()
+    }
+    /// how many values fall in each bucket of `buckets`, which are the largest `Value::len`
+    /// of each bucket in ascending order. the extra count on the end is for values bigger
+    /// than the last bucket, for picking `set_blob_threshold` or `set_value_compression`
+    pub fn value_size_histogram(&self, buckets: &[usize]) -> Vec<usize> {
+        let mut counts = vec![0; buckets.len() + 1];
+        for (_key, node) in self.iter() {
+            let len = node.value().len();
+            counts[buckets.partition_point(|max| *max < len)] += 1;
+        }
+        counts
+    }
+    /// how many `kind` connections go from a node in `a` to a node in `b`. declared kinds
+    /// search from whichever set is smaller since the other side of a connection can be
+    /// found from either end, kinds that aren't declared always search from `a`
+    pub fn edges_between(&self, a: &HashSet<Key>, b: &HashSet<Key>, kind: &str) -> usize {
+        // counts the keys of `from` that are in `other` without assuming which is bigger
+        let overlap = |from: &KeySet, other: &HashSet<Key>| {
+            if from.len() <= other.len() {
+                from.iter().filter(|key| other.contains(key)).count()
+            } else {
+                other.iter().filter(|key| from.contains(key)).count()
+            }
+        };
+        let backwards = match self.kind(kind) {
+            Some(Directionality::Directed) => {
+                Some(Node::get_incoming as for<'n> fn(&'n Node, &str) -> &'n KeySet)
+            }
+            Some(Directionality::Undirected) => {
+                Some(Node::get_connections as for<'n> fn(&'n Node, &str) -> &'n KeySet)
+            }
+            None => None,
+        };
+        match backwards {
+            Some(backwards) if b.len() < a.len() => b
+                .iter()
+                .filter_map(|key| self.get(key))
+                .map(|node| overlap(backwards(node, kind), a))
+                .sum(),
+            _ => a
+                .iter()
+                .filter_map(|key| self.get(key))
+                .map(|node| overlap(node.get_connections(kind), b))
+                .sum(),
+        }
+    }
+    /// `kind` connections over the number there could be with every node connected to every
+    /// other one, a connection both ways counts as two
+    pub fn density(&self, kind: &str) -> f64 {
+        let nodes = self.iter().count();
+        if nodes < 2 {
+            return 0.0;
+        }
+        let edges: usize = self
+            .iter()
+            .map(|(_key, node)| node.get_connections(kind).len())
+            .sum();
+        edges as f64 / (nodes * (nodes - 1)) as f64
+    }
+    /// longest shortest path through `kind` connections between any two nodes that can reach
+    /// each other, `None` for an empty database. does a breadth first search from every node
+    /// so it's O(V * (V + E)), see `approximate_diameter` for big graphs
+    pub fn diameter(&self, kind: &str) -> Option<usize> {
+        self.iter()
+            .map(|(key, _node)| self.eccentricity(key, kind))
+            .max()
+    }
+    /// `diameter` with the searches spread across threads
+    #[cfg(feature = "rayon")]
+    pub fn par_diameter(&self, kind: &str) -> Option<usize>
+    where
+        S: Sync,
+    {
+        self.inner
+            .par_iter()
+            .filter(|(_key, node)| !node.is_deleted())
+            .map(|(key, _node)| self.eccentricity(key, kind))
+            .max()
+    }
+    /// same as `diameter` but only searches from `samples` nodes spread out over the keys,
+    /// never more than the real diameter
+    pub fn approximate_diameter(&self, kind: &str, samples: usize) -> Option<usize> {
+        self.samples(samples)
+            .iter()
+            .map(|key| self.eccentricity(key, kind))
+            .max()
+    }
+    /// `approximate_diameter` with the searches spread across threads
+    #[cfg(feature = "rayon")]
+    pub fn par_approximate_diameter(&self, kind: &str, samples: usize) -> Option<usize>
+    where
+        S: Sync,
+    {
+        self.samples(samples)
+            .par_iter()
+            .map(|key| self.eccentricity(key, kind))
+            .max()
+    }
+    /// `samples` keys spread out evenly over the sorted keys
+    fn samples(&self, samples: usize) -> Vec<Key> {
+        let mut keys: Vec<Key> = self.iter().map(|(key, _node)| *key).collect();
+        keys.sort_unstable();
+        let step = keys.len().div_ceil(samples.max(1)).max(1);
+        keys.into_iter().step_by(step).collect()
+    }
+    /// how far the furthest node `start` reaches is
+    fn eccentricity(&self, start: &Key, kind: &str) -> usize {
+        let mut visited: HashSet<Key> = HashSet::from([*start]);
+        let mut ring: Vec<Key> = vec![*start];
+        let mut distance = 0;
+        loop {
+            ring = ring
+                .iter()
+                .flat_map(|key| self.select(key, kind))
+                .filter(|next| visited.insert(**next))
+                .copied()
+                .collect();
+            if ring.is_empty() {
+                return distance;
+            }
+            distance += 1;
+        }
+    }
+}
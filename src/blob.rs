@@ -0,0 +1,249 @@
+//! out of line storage for large values, their bytes go to a sidecar directory next to the
+//! database file and the snapshot only keeps the name of the blob
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    hash::BuildHasher,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use rkyv::{
+    Archive, Place, Serialize,
+    munge::munge,
+    rancor::Fallible,
+    with::{ArchiveWith, SerializeWith},
+};
+
+use crate::{ArchivedNode, ArchivedValue, AsSets, Key, KeySet, Node, Tombstone, Value, fnv1a};
+
+static NO_BYTES: Vec<u8> = Vec::new();
+
+/// borrowed version of `Node` that archives into the same `ArchivedNode`,
+/// so saving doesn't need to copy the graph to swap out the large values.
+/// `Archive` is implemented by hand because the derive can't resolve the archived types
+/// of borrowed fields
+pub(crate) struct StoredNode<'a> {
+    value: StoredValue<'a>,
+    connections: &'a HashMap<String, KeySet>,
+    incoming: &'a HashMap<String, KeySet>,
+    connected_at: &'a HashMap<String, HashMap<Key, i64>>,
+    weak: &'a HashMap<String, KeySet>,
+    touched_at: &'a Option<i64>,
+    tombstone: &'a Option<Tombstone>,
+}
+
+struct StoredValue<'a> {
+    bytes: &'a Vec<u8>,
+    tag: Option<u64>,
+    blob: Option<String>,
+    compressed: bool,
+}
+
+pub(crate) struct StoredNodeResolver {
+    value: StoredValueResolver,
+    connections: <AsSets as ArchiveWith<HashMap<String, KeySet>>>::Resolver,
+    incoming: <AsSets as ArchiveWith<HashMap<String, KeySet>>>::Resolver,
+    connected_at: <HashMap<String, HashMap<Key, i64>> as Archive>::Resolver,
+    weak: <AsSets as ArchiveWith<HashMap<String, KeySet>>>::Resolver,
+    touched_at: <Option<i64> as Archive>::Resolver,
+    tombstone: <Option<Tombstone> as Archive>::Resolver,
+}
+
+struct StoredValueResolver {
+    bytes: <Vec<u8> as Archive>::Resolver,
+    tag: <Option<u64> as Archive>::Resolver,
+    blob: <Option<String> as Archive>::Resolver,
+}
+
+impl Archive for StoredNode<'_> {
+    type Archived = ArchivedNode;
+    type Resolver = StoredNodeResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        munge!(let ArchivedNode { value, connections, incoming, connected_at, weak, touched_at, tombstone } = out);
+        self.value.resolve(resolver.value, value);
+        AsSets::resolve_with(self.connections, resolver.connections, connections);
+        AsSets::resolve_with(self.incoming, resolver.incoming, incoming);
+        self.connected_at
+            .resolve(resolver.connected_at, connected_at);
+        AsSets::resolve_with(self.weak, resolver.weak, weak);
+        self.touched_at.resolve(resolver.touched_at, touched_at);
+        self.tombstone.resolve(resolver.tombstone, tombstone);
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for StoredNode<'_>
+where
+    Vec<u8>: Serialize<S>,
+    Option<u64>: Serialize<S>,
+    Option<String>: Serialize<S>,
+    AsSets: SerializeWith<HashMap<String, KeySet>, S>,
+    HashMap<String, HashMap<Key, i64>>: Serialize<S>,
+    Option<i64>: Serialize<S>,
+    Option<Tombstone>: Serialize<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(StoredNodeResolver {
+            value: self.value.serialize(serializer)?,
+            connections: AsSets::serialize_with(self.connections, serializer)?,
+            incoming: AsSets::serialize_with(self.incoming, serializer)?,
+            connected_at: self.connected_at.serialize(serializer)?,
+            weak: AsSets::serialize_with(self.weak, serializer)?,
+            touched_at: self.touched_at.serialize(serializer)?,
+            tombstone: self.tombstone.serialize(serializer)?,
+        })
+    }
+}
+
+impl Archive for StoredValue<'_> {
+    type Archived = ArchivedValue;
+    type Resolver = StoredValueResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        munge!(let ArchivedValue { bytes, tag, blob, compressed, .. } = out);
+        self.bytes.resolve(resolver.bytes, bytes);
+        self.tag.resolve(resolver.tag, tag);
+        self.blob.resolve(resolver.blob, blob);
+        self.compressed.resolve((), compressed);
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for StoredValue<'_>
+where
+    Vec<u8>: Serialize<S>,
+    Option<u64>: Serialize<S>,
+    Option<String>: Serialize<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(StoredValueResolver {
+            bytes: self.bytes.serialize(serializer)?,
+            tag: self.tag.serialize(serializer)?,
+            blob: self.blob.serialize(serializer)?,
+        })
+    }
+}
+
+/// `db.grahh` keeps its blobs in `db.grahh.blobs`
+pub(crate) fn dir(path: &Path) -> PathBuf {
+    let mut dir = path.as_os_str().to_owned();
+    dir.push(".blobs");
+    PathBuf::from(dir)
+}
+
+/// blobs are content addressed so unchanged values aren't written again on every save. the
+/// hash has to be the same in every build since the names are saved, and the length is part
+/// of the name to make a collision even less likely
+fn name(bytes: &[u8]) -> String {
+    format!("{:016x}-{:x}", fnv1a(bytes), bytes.len())
+}
+
+/// name of a blob in `dir` with the bytes of `value`, writing it if there isn't one yet.
+/// a blob with the right name is only reused if it holds the same bytes, otherwise it's a
+/// collision or a blob a crash cut short and the value goes under the next free `.1`, `.2`
+fn write(dir: &Path, value: &Value, durable: bool) -> io::Result<String> {
+    let len = value.bytes.len() as u64;
+    // a blob the value was loaded from or written to before, as long as it's still whole
+    let known = value.blob.as_ref().or(value.written.0.get());
+    if let Some(blob) = known
+        && fs::metadata(dir.join(blob)).is_ok_and(|metadata| metadata.len() == len)
+    {
+        return Ok(blob.clone());
+    }
+    let base = name(&value.bytes);
+    let mut blob = base.clone();
+    for n in 1.. {
+        let path = dir.join(&blob);
+        match fs::read(&path) {
+            Ok(existing) if existing == value.bytes => break,
+            Ok(_) => blob = format!("{base}.{n}"),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                fs::create_dir_all(dir)?;
+                let mut file = File::create(path)?;
+                file.write_all(&value.bytes)?;
+                if durable {
+                    file.sync_all()?;
+                }
+                break;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    let _ = value.written.0.set(blob.clone());
+    Ok(blob)
+}
+
+/// view of `data` where every value longer than the threshold is written out to the blob
+/// directory, `None` keeps everything inline. `durable` syncs every blob that gets written
+pub(crate) fn externalize<'a, H>(
+    data: &'a HashMap<Key, Node, H>,
+    blobs: Option<(&Path, usize)>,
+    durable: bool,
+) -> io::Result<HashMap<Key, StoredNode<'a>>> {
+    data.iter()
+        .map(|(key, node)| {
+            let value = match blobs {
+                Some((dir, threshold)) if node.value.len() > threshold => {
+                    let blob = write(dir, &node.value, durable)?;
+                    StoredValue {
+                        bytes: &NO_BYTES,
+                        tag: node.value.tag,
+                        blob: Some(blob),
+                        compressed: node.value.compressed,
+                    }
+                }
+                _ => StoredValue {
+                    bytes: &node.value.bytes,
+                    tag: node.value.tag,
+                    blob: None,
+                    compressed: node.value.compressed,
+                },
+            };
+            let node = StoredNode {
+                value,
+                connections: &node.connections,
+                incoming: &node.incoming,
+                connected_at: &node.connected_at,
+                weak: &node.weak,
+                touched_at: &node.touched_at,
+                tombstone: &node.tombstone,
+            };
+            Ok((*key, node))
+        })
+        .collect()
+}
+
+/// removes blobs that `stored` doesn't reference, only call this once `stored` is on disk
+/// so the previous snapshot can still find its blobs if saving fails midway
+pub(crate) fn remove_unreferenced(stored: &HashMap<Key, StoredNode>, dir: &Path) -> io::Result<()> {
+    let referenced: HashSet<&str> = stored
+        .values()
+        .filter_map(|node| node.value.blob.as_deref())
+        .collect();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error),
+    };
+    for entry in entries {
+        let entry = entry?;
+        if !referenced.contains(entry.file_name().to_string_lossy().as_ref()) {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// loads the bytes of every value that was stored out of line
+pub(crate) fn resolve<H: BuildHasher>(
+    data: &mut HashMap<Key, Node, H>,
+    dir: &Path,
+) -> io::Result<()> {
+    for node in data.values_mut() {
+        if let Some(blob) = &node.value.blob {
+            node.value.bytes = fs::read(dir.join(blob))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap, hash::BuildHasher, marker::PhantomData, mem, path::PathBuf, sync::Arc,
+};
+
+use crate::{
+    Database, Directionality, KeyGenerator, LoadError, NodeHasher, OnDrop, SaveOnDrop, Storage,
+};
+
+/// configuration for a `Database`, finished with `build` for one in memory or `open` for one
+/// backed by a file. `Database::in_memory` and `Database::load` are the same as building with
+/// nothing set
+#[derive(Debug, Clone)]
+pub struct DatabaseBuilder<S = NodeHasher> {
+    capacity: usize,
+    blob_threshold: Option<usize>,
+    max_value_bytes: Option<usize>,
+    max_nodes: Option<usize>,
+    kinds: HashMap<String, Directionality>,
+    edge_timestamps: bool,
+    unique_targets: bool,
+    durable: bool,
+    save_on_drop: SaveOnDrop,
+    backups: usize,
+    keys: Option<Arc<dyn KeyGenerator>>,
+    #[cfg(feature = "zstd")]
+    compression: Option<i32>,
+    #[cfg(feature = "zstd")]
+    value_compression: Option<(usize, i32)>,
+    hasher: PhantomData<S>,
+}
+
+impl DatabaseBuilder {
+    pub fn new() -> Self {
+        Self {
+            capacity: 0,
+            blob_threshold: None,
+            max_value_bytes: None,
+            max_nodes: None,
+            kinds: HashMap::new(),
+            edge_timestamps: false,
+            unique_targets: false,
+            durable: false,
+            save_on_drop: SaveOnDrop::Off,
+            backups: 0,
+            keys: None,
+            #[cfg(feature = "zstd")]
+            compression: None,
+            #[cfg(feature = "zstd")]
+            value_compression: None,
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl Default for DatabaseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> DatabaseBuilder<S> {
+    /// hashes the node map with `T` instead, see `NodeHasher`
+    pub fn hasher<T>(self) -> DatabaseBuilder<T> {
+        DatabaseBuilder {
+            capacity: self.capacity,
+            blob_threshold: self.blob_threshold,
+            max_value_bytes: self.max_value_bytes,
+            max_nodes: self.max_nodes,
+            kinds: self.kinds,
+            edge_timestamps: self.edge_timestamps,
+            unique_targets: self.unique_targets,
+            durable: self.durable,
+            save_on_drop: self.save_on_drop,
+            backups: self.backups,
+            keys: self.keys,
+            #[cfg(feature = "zstd")]
+            compression: self.compression,
+            #[cfg(feature = "zstd")]
+            value_compression: self.value_compression,
+            hasher: PhantomData,
+        }
+    }
+    /// room for this many nodes on top of the ones that get loaded
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+    /// see `Database::set_blob_threshold`
+    pub fn blob_threshold(mut self, threshold: usize) -> Self {
+        self.blob_threshold = Some(threshold);
+        self
+    }
+    /// see `Database::set_max_value_bytes`
+    pub fn max_value_bytes(mut self, max: usize) -> Self {
+        self.max_value_bytes = Some(max);
+        self
+    }
+    /// see `Database::set_max_nodes`, a file with more nodes than `max` still loads but
+    /// nothing can be added to it
+    pub fn max_nodes(mut self, max: usize) -> Self {
+        self.max_nodes = Some(max);
+        self
+    }
+    /// see `Database::declare_kind`, kinds declared here are in place before indexes get
+    /// rebuilt on load
+    pub fn declare_kind(mut self, kind: impl Into<String>, directionality: Directionality) -> Self {
+        self.kinds.insert(kind.into(), directionality);
+        self
+    }
+    /// see `Database::set_edge_timestamps`
+    pub fn edge_timestamps(mut self, enabled: bool) -> Self {
+        self.edge_timestamps = enabled;
+        self
+    }
+    /// see `Database::set_unique_targets`
+    pub fn unique_targets(mut self, enabled: bool) -> Self {
+        self.unique_targets = enabled;
+        self
+    }
+    /// see `Database::set_durable`
+    pub fn durable(mut self, durable: bool) -> Self {
+        self.durable = durable;
+        self
+    }
+    /// see `Database::set_save_on_drop`
+    pub fn save_on_drop(mut self, save_on_drop: SaveOnDrop) -> Self {
+        self.save_on_drop = save_on_drop;
+        self
+    }
+    /// see `Database::set_backups`
+    pub fn backups(mut self, count: usize) -> Self {
+        self.backups = count;
+        self
+    }
+    /// see `Database::set_key_generator`
+    pub fn key_generator(mut self, keys: impl KeyGenerator + 'static) -> Self {
+        self.keys = Some(Arc::new(keys));
+        self
+    }
+    /// zstd compress the database file at `level`, see `Storage::CompressedFile`
+    #[cfg(feature = "zstd")]
+    pub fn compression(mut self, level: i32) -> Self {
+        self.compression = Some(level);
+        self
+    }
+    /// see `Database::set_value_compression`
+    #[cfg(feature = "zstd")]
+    pub fn value_compression(mut self, threshold: usize, level: i32) -> Self {
+        self.value_compression = Some((threshold, level));
+        self
+    }
+}
+
+impl<S: BuildHasher + Default> DatabaseBuilder<S> {
+    pub fn build(mut self) -> Database<S> {
+        let mut db = Database::in_memory_with_hasher();
+        db.kinds = mem::take(&mut self.kinds);
+        self.configure(db)
+    }
+    /// panics if the file can't be read or isn't a database, see `try_open`
+    pub fn open(self, path: PathBuf) -> Database<S> {
+        self.try_open(path).unwrap()
+    }
+    pub fn try_open(mut self, path: PathBuf) -> Result<Database<S>, LoadError> {
+        #[cfg(feature = "zstd")]
+        let storage = match self.compression {
+            Some(level) => Storage::CompressedFile { path, level },
+            None => Storage::File(path),
+        };
+        #[cfg(not(feature = "zstd"))]
+        let storage = Storage::File(path);
+        let db = Database::open(storage, mem::take(&mut self.kinds))?;
+        Ok(self.configure(db))
+    }
+    fn configure(self, mut db: Database<S>) -> Database<S> {
+        db.inner.reserve(self.capacity);
+        // making room doesn't change anything that needs saving
+        db.inner.clean();
+        db.blob_threshold = self.blob_threshold;
+        db.checks.max_value_bytes = self.max_value_bytes;
+        db.max_nodes = self.max_nodes;
+        db.edge_timestamps = self.edge_timestamps;
+        db.unique_targets = self.unique_targets;
+        db.durable = self.durable;
+        db.save_on_drop = OnDrop(self.save_on_drop);
+        db.backups = self.backups;
+        #[cfg(feature = "zstd")]
+        {
+            db.checks.compression = self.value_compression;
+        }
+        if let Some(keys) = self.keys {
+            db.keys = keys;
+            db.reserve_keys();
+        }
+        db
+    }
+}
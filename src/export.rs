@@ -0,0 +1,242 @@
+use std::{collections::HashMap, fmt::Write, hash::BuildHasher, io};
+
+#[cfg(feature = "petgraph")]
+use petgraph::graph::NodeIndex;
+
+use crate::{Database, Key, Value};
+
+/// iri for a node, `urn:grahh:key:` and the number of the key
+fn subject(key: Key) -> String {
+    format!("<urn:grahh:key:{}>", key.0)
+}
+
+/// iri for a kind, anything iris can't hold gets percent encoded
+fn predicate(kind: &str) -> String {
+    let mut iri = String::from("<urn:grahh:kind:");
+    for byte in kind.bytes() {
+        if byte.is_ascii_graphic() && !b"<>\"{}|^`\\%".contains(&byte) {
+            iri.push(char::from(byte));
+        } else {
+            let _ = write!(iri, "%{byte:02X}");
+        }
+    }
+    iri.push('>');
+    iri
+}
+
+/// quotes a csv field if it has to be
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_owned()
+    }
+}
+
+fn literal(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// json string with everything json can't hold escaped
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", u32::from(c));
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | u32::from(*byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(
+                    ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize],
+                ));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+impl<S: BuildHasher> Database<S> {
+    /// every connection as an N-Triples line, keys are `urn:grahh:key:` iris and kinds are
+    /// `urn:grahh:kind:` iris
+    pub fn to_ntriples(&self) -> String {
+        self.to_ntriples_with(|_value| None)
+    }
+    /// same as `to_ntriples` but also emits a `urn:grahh:value` literal for every value
+    /// `stringify` turns into a string
+    pub fn to_ntriples_with(&self, stringify: impl Fn(&Value) -> Option<String>) -> String {
+        let mut out = String::new();
+        for (source, kind, target) in self.edges(false) {
+            let _ = writeln!(
+                out,
+                "{} {} {} .",
+                subject(source),
+                predicate(kind),
+                subject(target)
+            );
+        }
+        for (key, node) in self.iter() {
+            if let Some(text) = stringify(node.value()) {
+                let _ = writeln!(
+                    out,
+                    "{} <urn:grahh:value> {} .",
+                    subject(*key),
+                    literal(&text)
+                );
+            }
+        }
+        out
+    }
+    /// `source,kind,target` csv with a header row, keys are written as the numbers
+    /// `Key::parse` takes
+    pub fn to_csv_edges(&self) -> String {
+        let mut out = String::from("source,kind,target\n");
+        for (source, kind, target) in self.edges(false) {
+            let _ = writeln!(out, "{},{},{}", source.0, field(kind), target.0);
+        }
+        out
+    }
+    /// `key,value_len` csv with a header row
+    pub fn to_csv_nodes(&self) -> String {
+        let mut out = String::from("key,value_len\n");
+        for (key, node) in self.iter() {
+            let _ = writeln!(out, "{},{}", key.0, node.value().len());
+        }
+        out
+    }
+    /// one json object per line per node, nodes in key order, with the key as a string since
+    /// keys don't fit in the integers most json readers use, the rkyv bytes of the value as
+    /// base64 and the connections by kind, kinds and keys sorted so the same database always
+    /// writes the same lines:
+    /// `{"key":"1","value":"AQ==","connections":{"friend":["2","3"]}}`.
+    /// compressed values are written decompressed
+    pub fn write_jsonl<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut keys: Vec<Key> = self.iter().map(|(key, _node)| *key).collect();
+        keys.sort_unstable();
+        let mut line = String::new();
+        for key in keys {
+            let node = &self.inner[&key];
+            line.clear();
+            let _ = write!(
+                line,
+                "{{\"key\":\"{}\",\"value\":\"{}\",\"connections\":{{",
+                key.0,
+                base64(node.value.as_bytes()?)
+            );
+            let mut kinds: Vec<(&str, usize)> = node.connections().collect();
+            kinds.sort_unstable();
+            for (i, (kind, _len)) in kinds.into_iter().enumerate() {
+                let mut targets: Vec<Key> = node.get_connections(kind).iter().copied().collect();
+                targets.sort_unstable();
+                let targets: Vec<String> = targets
+                    .iter()
+                    .map(|target| format!("\"{}\"", target.0))
+                    .collect();
+                let comma = if i == 0 { "" } else { "," };
+                let _ = write!(line, "{comma}{}:[{}]", json_string(kind), targets.join(","));
+            }
+            line.push_str("}}\n");
+            w.write_all(line.as_bytes())?;
+        }
+        Ok(())
+    }
+    /// neighbors of every node under `kind`, nodes without any get an empty list. `sorted`
+    /// sorts each list so it comes out the same every time
+    pub fn adjacency_list(&self, kind: &str, sorted: bool) -> HashMap<Key, Vec<Key>> {
+        self.iter()
+            .map(|(key, node)| {
+                let mut neighbors: Vec<Key> = node.get_connections(kind).iter().copied().collect();
+                if sorted {
+                    neighbors.sort_unstable();
+                }
+                (*key, neighbors)
+            })
+            .collect()
+    }
+    /// sorted keys and a matrix where `matrix[i][j]` is whether `keys[i]` connects to
+    /// `keys[j]` under `kind`, see `sparse_adjacency` for big graphs
+    pub fn adjacency_matrix(&self, kind: &str) -> (Vec<Key>, Vec<Vec<bool>>) {
+        let (keys, pairs) = self.sparse_adjacency(kind);
+        let mut matrix = vec![vec![false; keys.len()]; keys.len()];
+        for (row, column) in pairs {
+            matrix[row][column] = true;
+        }
+        (keys, matrix)
+    }
+    /// graph of every node with an edge for each `kind` connection, nodes are added in key
+    /// order and weighted with their key, along with the index each key got. a connection
+    /// under an undirected kind is an edge both ways
+    #[cfg(feature = "petgraph")]
+    pub fn to_petgraph(&self, kind: &str) -> (petgraph::Graph<Key, ()>, HashMap<Key, NodeIndex>) {
+        let mut keys: Vec<Key> = self.iter().map(|(key, _node)| *key).collect();
+        keys.sort_unstable();
+        let mut graph = petgraph::Graph::with_capacity(keys.len(), 0);
+        let index: HashMap<Key, NodeIndex> = keys
+            .iter()
+            .map(|key| (*key, graph.add_node(*key)))
+            .collect();
+        for key in &keys {
+            let mut targets: Vec<Key> = self.select(key, kind).iter().copied().collect();
+            targets.sort_unstable();
+            for target in targets {
+                if let Some(target) = index.get(&target) {
+                    graph.add_edge(index[key], *target, ());
+                }
+            }
+        }
+        (graph, index)
+    }
+    /// sorted keys and the `(i, j)` index pairs of every `kind` connection from `keys[i]`
+    /// to `keys[j]`, sorted too
+    pub fn sparse_adjacency(&self, kind: &str) -> (Vec<Key>, Vec<(usize, usize)>) {
+        let mut keys: Vec<Key> = self.iter().map(|(key, _node)| *key).collect();
+        keys.sort_unstable();
+        let index: HashMap<Key, usize> =
+            keys.iter().enumerate().map(|(i, key)| (*key, i)).collect();
+        let index = &index;
+        let mut pairs: Vec<(usize, usize)> = keys
+            .iter()
+            .enumerate()
+            .flat_map(|(row, key)| {
+                self.select(key, kind)
+                    .iter()
+                    .filter_map(move |target| index.get(target).map(|column| (row, *column)))
+            })
+            .collect();
+        pairs.sort_unstable();
+        (keys, pairs)
+    }
+}
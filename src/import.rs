@@ -0,0 +1,194 @@
+use std::{
+    collections::{HashMap, HashSet, hash_map},
+    fs::File,
+    hash::BuildHasher,
+    mem,
+    path::Path,
+};
+
+use thiserror::Error;
+
+#[cfg(feature = "petgraph")]
+use rkyv::{api::high::HighSerializer, rancor, ser::allocator::ArenaHandle, util::AlignedVec};
+
+#[cfg(feature = "petgraph")]
+use crate::Directionality;
+use crate::{CreateError, Database, Key, KeyParseError, LoadError, Node, Value, blob, snapshot};
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("line {line}: expected 3 columns, found {found}")]
+    Columns { line: usize, found: usize },
+    #[error("line {line}: invalid key")]
+    Key {
+        line: usize,
+        #[source]
+        source: KeyParseError,
+    },
+    #[error("line {line}: quote is never closed")]
+    Quote { line: usize },
+    #[error("failed to read the database to import")]
+    Load(#[from] LoadError),
+    #[error("failed to add an imported node")]
+    Create(#[from] CreateError),
+}
+
+/// splits csv into rows of fields, each with the line the row starts on
+fn rows(csv: &str) -> Result<Vec<(usize, Vec<String>)>, ImportError> {
+    let mut rows: Vec<(usize, Vec<String>)> = Vec::new();
+    let mut chars = csv.chars().peekable();
+    let mut line = 1;
+    while chars.peek().is_some() {
+        let start = line;
+        let mut fields: Vec<String> = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        loop {
+            match chars.next() {
+                None if quoted => return Err(ImportError::Quote { line: start }),
+                None => break,
+                Some('"') if quoted => {
+                    if chars.next_if_eq(&'"').is_some() {
+                        field.push('"');
+                    } else {
+                        quoted = false;
+                    }
+                }
+                Some('"') if field.is_empty() => quoted = true,
+                Some(',') if !quoted => fields.push(mem::take(&mut field)),
+                Some('\r') if !quoted && chars.peek() == Some(&'\n') => {}
+                Some('\n') if !quoted => {
+                    line += 1;
+                    break;
+                }
+                Some(c) => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+        }
+        fields.push(field);
+        // blank lines
+        if fields != [""] {
+            rows.push((start, fields));
+        }
+    }
+    Ok(rows)
+}
+
+impl<S: BuildHasher> Database<S> {
+    /// connects the keys of every `source,kind,target` row under its kind with `connect`,
+    /// `kind_default` for rows with an empty kind. keys that aren't in the database yet get
+    /// a node with an empty value. rows connecting a key to itself and the header
+    /// `to_csv_edges` writes are skipped, nothing is imported if any row is malformed or the
+    /// new nodes don't fit under `set_max_nodes`. returns how many connections were made
+    pub fn import_csv_edges(
+        &mut self,
+        csv: &str,
+        kind_default: &str,
+    ) -> Result<usize, ImportError> {
+        let mut edges: Vec<(Key, String, Key)> = Vec::new();
+        for (line, fields) in rows(csv)? {
+            if line == 1 && fields == ["source", "kind", "target"] {
+                continue;
+            }
+            let [source, kind, target] =
+                <[String; 3]>::try_from(fields).map_err(|fields| ImportError::Columns {
+                    line,
+                    found: fields.len(),
+                })?;
+            let parse =
+                |key: &str| Key::parse(key).map_err(|source| ImportError::Key { line, source });
+            let kind = if kind.is_empty() {
+                kind_default.to_owned()
+            } else {
+                kind
+            };
+            edges.push((parse(&source)?, kind, parse(&target)?));
+        }
+        let missing: HashSet<Key> = edges
+            .iter()
+            .flat_map(|(source, _kind, target)| [*source, *target])
+            .filter(|key| !self.inner.contains_key(key))
+            .collect();
+        self.room(missing.len()).map_err(CreateError::from)?;
+        let mut connected = 0;
+        for (source, kind, target) in edges {
+            for key in [source, target] {
+                if let hash_map::Entry::Vacant(entry) = self.inner.entry(key) {
+                    self.keys.reserve(key);
+                    entry.insert(Node::from_value(Value::from_bytes(Vec::new())));
+                }
+            }
+            if source != target && self.connect(source, kind.clone(), target, kind) {
+                connected += 1;
+            }
+        }
+        Ok(connected)
+    }
+    /// adds every node saved in the database file at `path` under a new key, along with the
+    /// connections between them, and returns which new key each old one got. the file is
+    /// only read, this database keeps its own storage. soft removed nodes are left out and
+    /// values aren't checked, same as `import_csv_edges`. new keys are generated the same way
+    /// `create` does it, retries included. nothing is added if it fails, including when the
+    /// nodes don't fit under `set_max_nodes`
+    pub fn import_file(&mut self, path: &Path) -> Result<HashMap<Key, Key>, ImportError> {
+        let mut file = File::open(path).map_err(LoadError::from)?;
+        let (mut nodes, _bytes): (HashMap<Key, Node>, usize) = snapshot::read(&mut file)?;
+        blob::resolve(&mut nodes, &blob::dir(path)).map_err(LoadError::from)?;
+        nodes.retain(|_key, node| !node.is_deleted());
+        self.room(nodes.len()).map_err(CreateError::from)?;
+        let mut keys: HashMap<Key, Key> = HashMap::with_capacity(nodes.len());
+        // every node goes in as soon as it has a key, so the retries also step around the
+        // keys given to the nodes imported before it
+        for (old, node) in nodes {
+            match self.insert_generated(node) {
+                Ok(new) => {
+                    keys.insert(old, new);
+                }
+                Err(error) => {
+                    for new in keys.values() {
+                        self.inner.remove(new);
+                    }
+                    return Err(error.into());
+                }
+            }
+        }
+        for new in keys.values() {
+            self.inner.get_mut(new).unwrap().remap(&keys);
+        }
+        Ok(keys)
+    }
+}
+
+impl Database {
+    /// in-memory database with a node for every node of `graph`, its weight serialized as the
+    /// value, and a connection under `kind` for every edge. `kind` is declared directed or
+    /// undirected to match the graph. edges from a node to itself are left out since nodes
+    /// can't connect to themselves, and edges between the same two nodes become the one
+    /// connection
+    #[cfg(feature = "petgraph")]
+    pub fn from_petgraph<N, Ty>(graph: &petgraph::Graph<N, (), Ty>, kind: &str) -> Database
+    where
+        N: for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+        Ty: petgraph::EdgeType,
+    {
+        let mut db = Database::in_memory();
+        let directionality = if Ty::is_directed() {
+            Directionality::Directed
+        } else {
+            Directionality::Undirected
+        };
+        db.declare_kind(kind, directionality);
+        // node indices are always `0..node_count` so keys can be looked up by index
+        let keys: Vec<Key> = db.create_many(graph.node_weights());
+        for edge in graph.raw_edges() {
+            let source = keys[edge.source().index()];
+            let target = keys[edge.target().index()];
+            db.connect(source, kind, target, kind);
+        }
+        db
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    fmt::Debug,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::Key;
+
+/// how a database picks keys for the nodes it creates, `TimestampKeys` unless
+/// `Database::set_key_generator` or the builder says otherwise
+pub trait KeyGenerator: Debug + Send + Sync {
+    /// a key that wasn't handed out before, `None` once there are none left
+    fn generate(&self) -> Option<Key>;
+    /// called with keys that get into the database some other way, like `create_with_key`
+    /// or loading, so `generate` can stay clear of them
+    fn reserve(&self, _key: Key) {}
+}
+
+/// nanoseconds since the epoch so keys sort by when they were created, like `Key::generate`
+/// but with one counter per generator so databases don't push each other's keys along.
+/// `reserve` ignores keys more than a day ahead of the clock, those didn't come from a clock
+/// and following one would leave every later key far in the future. if the clock catches up
+/// with one of them creating that node just tries the next key
+#[derive(Debug, Default)]
+pub struct TimestampKeys(AtomicU64);
+
+/// how far ahead of the clock `TimestampKeys::reserve` still follows a key, in nanoseconds
+const AHEAD: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+impl KeyGenerator for TimestampKeys {
+    fn generate(&self) -> Option<Key> {
+        Key::timestamp_after(&self.0)
+    }
+    fn reserve(&self, key: Key) {
+        if key.0 <= Key::now().saturating_add(AHEAD) {
+            self.0.fetch_max(key.0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 1, 2, 3 and so on, one counter per generator. still sorts by creation but doesn't tell
+/// when a node was created
+#[derive(Debug, Default)]
+pub struct CounterKeys(AtomicU64);
+
+impl KeyGenerator for CounterKeys {
+    fn generate(&self) -> Option<Key> {
+        let last = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                last.checked_add(1)
+            })
+            .ok()?;
+        Some(Key(last + 1))
+    }
+    fn reserve(&self, key: Key) {
+        self.0.fetch_max(key.0, Ordering::Relaxed);
+    }
+}
+
+/// random keys from the operating system, so they can't be guessed from each other. they
+/// don't sort by anything, `Database::min_key`, `keys_in_range` and friends lose their
+/// meaning. creating a node tries another key if one collides, with 64 bits that takes
+/// around 4 billion nodes to become likely
+#[cfg(feature = "rand")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomKeys;
+
+#[cfg(feature = "rand")]
+impl KeyGenerator for RandomKeys {
+    fn generate(&self) -> Option<Key> {
+        use rand::TryRng;
+
+        rand::rngs::SysRng.try_next_u64().ok().map(Key)
+    }
+}
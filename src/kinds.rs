@@ -0,0 +1,119 @@
+use std::hash::BuildHasher;
+
+use crate::{Database, Directionality, Key, KeySet};
+
+/// generates an enum of connection kinds so a typo in a kind is a compile error instead of a
+/// connection nobody selects. each variant maps to the string it's stored as
+///
+/// ```
+/// grahh_db::define_kinds! {
+///     pub enum Kinds {
+///         Friend = "friend",
+///         Parent = "parent",
+///     }
+/// }
+///
+/// let mut db = grahh_db::Database::in_memory();
+/// let (a, b) = (db.create(&1_u32), db.create(&2_u32));
+/// db.connect(a, Kinds::Friend, b, Kinds::Friend);
+/// assert!(db.select(&a, &Kinds::Friend).contains(&b));
+/// assert_eq!(Kinds::parse("parent"), Some(Kinds::Parent));
+/// ```
+#[macro_export]
+macro_rules! define_kinds {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $kind:literal),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant,)*
+        }
+
+        impl $name {
+            pub const ALL: &'static [Self] = &[$(Self::$variant),*];
+            pub const fn as_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $kind,)*
+                }
+            }
+            /// the variant stored as `kind`, for checking kinds read back from the database
+            pub fn parse(kind: &str) -> ::core::option::Option<Self> {
+                match kind {
+                    $($kind => ::core::option::Option::Some(Self::$variant),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+
+        impl ::core::ops::Deref for $name {
+            type Target = str;
+            fn deref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl ::core::convert::From<$name> for ::std::string::String {
+            fn from(kind: $name) -> Self {
+                kind.as_str().into()
+            }
+        }
+    };
+}
+
+/// a connection kind that's known at compile time, implemented on a unit struct per kind so
+/// `Database::connect_typed` can't be handed a misspelled kind or the wrong directionality
+///
+/// ```
+/// use grahh_db::KindMarker;
+///
+/// struct Follows;
+///
+/// impl KindMarker for Follows {
+///     const NAME: &'static str = "follows";
+///     const DIRECTED: bool = true;
+/// }
+///
+/// let mut db = grahh_db::Database::in_memory();
+/// let (a, b) = (db.create(&1_u32), db.create(&2_u32));
+/// assert!(db.connect_typed::<Follows>(a, b));
+/// assert!(db.select_typed::<Follows>(&a).contains(&b));
+/// assert!(db.select_typed::<Follows>(&b).is_empty());
+/// ```
+pub trait KindMarker {
+    /// the string the kind is stored as
+    const NAME: &'static str;
+    /// `Directionality::Directed` when true, otherwise `Directionality::Undirected`
+    const DIRECTED: bool;
+}
+
+impl<S: BuildHasher> Database<S> {
+    /// `connect(a, K::NAME, b, K::NAME)`, declaring the kind first if it isn't declared yet.
+    /// false if `connect` would be or if the kind is already declared the other way
+    pub fn connect_typed<K: KindMarker>(&mut self, a: Key, b: Key) -> bool {
+        let directionality = if K::DIRECTED {
+            Directionality::Directed
+        } else {
+            Directionality::Undirected
+        };
+        match self.kind(K::NAME) {
+            None => self.declare_kind(K::NAME, directionality),
+            Some(declared) if declared != directionality => return false,
+            Some(_) => {}
+        }
+        self.connect(a, K::NAME, b, K::NAME)
+    }
+    /// `select(key, K::NAME)`
+    pub fn select_typed<K: KindMarker>(&self, key: &Key) -> &KeySet {
+        self.select(key, K::NAME)
+    }
+}
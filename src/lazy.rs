@@ -0,0 +1,275 @@
+//! indexed file format that can be opened without reading every node. the file is a header,
+//! an index with the offset of every node and then the nodes, each archived on its own:
+//!
+//! ```text
+//! magic     b"grahhidx"
+//! version   u32
+//! count     u64
+//! index     count * (key u64, offset u64, len u64), sorted by key, offsets start at the
+//!           first byte after the index
+//! nodes     the archived nodes back to back
+//! ```
+//!
+//! every number is little endian. soft removed nodes aren't written
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    hash::BuildHasher,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
+
+use rkyv::{rancor, util::AlignedVec};
+
+use crate::{
+    ArchivedNode, Database, Key, LoadError, Node, SalvageError, SaveError, blob, parent, suffixed,
+    sync_dir,
+};
+
+const MAGIC: &[u8; 8] = b"grahhidx";
+/// 2 saves connections as lists of keys instead of sets
+const VERSION: u32 = 2;
+const HEADER: u64 = 8 + 4 + 8;
+const ENTRY: u64 = 8 * 3;
+
+/// where a node's bytes are in the node region
+#[derive(Debug, Clone, Copy)]
+struct Location {
+    offset: u64,
+    len: u64,
+}
+
+/// read only database in a file written by `Database::save_indexed`. opening it only reads
+/// the index, every `get` reads its node from the file
+#[derive(Debug)]
+pub struct LazyDatabase {
+    /// sorted by key for binary searching
+    index: Vec<(Key, Location)>,
+    /// `get` only borrows so seeking needs a lock
+    file: Mutex<File>,
+    nodes_start: u64,
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+impl LazyDatabase {
+    /// fails with `LoadError::Index` if the index isn't sorted or points past the end of the
+    /// file, so `get` never reads more than the file holds
+    pub fn open(path: &Path) -> Result<Self, LoadError> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut r = BufReader::new(file);
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(LoadError::NotIndexed);
+        }
+        let version = read_u32(&mut r)?;
+        if version != VERSION {
+            return Err(LoadError::Version(version));
+        }
+        let count = read_u64(&mut r)?;
+        let nodes_start = count
+            .checked_mul(ENTRY)
+            .and_then(|index| index.checked_add(HEADER))
+            .filter(|nodes_start| *nodes_start <= file_len)
+            .ok_or(LoadError::Index)?;
+        let index: Vec<(Key, Location)> = (0..count)
+            .map(|_| {
+                let key = Key(read_u64(&mut r)?);
+                let offset = read_u64(&mut r)?;
+                let len = read_u64(&mut r)?;
+                Ok((key, Location { offset, len }))
+            })
+            .collect::<io::Result<_>>()?;
+        let region = file_len - nodes_start;
+        let in_file = |(_key, location): &(Key, Location)| {
+            location
+                .offset
+                .checked_add(location.len)
+                .is_some_and(|end| end <= region)
+        };
+        let sorted = index.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        if !sorted || !index.iter().all(in_file) {
+            return Err(LoadError::Index);
+        }
+        Ok(Self {
+            index,
+            file: Mutex::new(r.into_inner()),
+            nodes_start,
+        })
+    }
+    fn location(&self, key: &Key) -> Option<Location> {
+        let i = self
+            .index
+            .binary_search_by_key(key, |(key, _location)| *key)
+            .ok()?;
+        Some(self.index[i].1)
+    }
+    /// reads the node from the file, `None` if it isn't in the index
+    pub fn get(&self, key: &Key) -> Result<Option<Node>, LoadError> {
+        let Some(location) = self.location(key) else {
+            return Ok(None);
+        };
+        let mut bytes: AlignedVec = AlignedVec::with_capacity(location.len as usize);
+        bytes.resize(location.len as usize, 0);
+        {
+            let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+            file.seek(SeekFrom::Start(self.nodes_start + location.offset))?;
+            file.read_exact(&mut bytes)?;
+        }
+        let archived = rkyv::access::<ArchivedNode, rancor::Error>(&bytes)?;
+        Ok(Some(rkyv::deserialize::<Node, rancor::Error>(archived)?))
+    }
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.location(key).is_some()
+    }
+    /// every key in the index in order
+    pub fn keys(&self) -> impl Iterator<Item = Key> {
+        self.index.iter().map(|(key, _location)| *key)
+    }
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+    /// reads every node into an in-memory database you can change
+    pub fn to_database(&self) -> Result<Database, LoadError> {
+        let mut db = Database::in_memory();
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        file.seek(SeekFrom::Start(self.nodes_start))?;
+        let mut r = BufReader::new(&mut *file);
+        let mut bytes = AlignedVec::<16>::new();
+        // the nodes were written in key order so the region is read front to back
+        for (key, location) in &self.index {
+            bytes.clear();
+            bytes.resize(location.len as usize, 0);
+            r.read_exact(&mut bytes)?;
+            let archived = rkyv::access::<ArchivedNode, rancor::Error>(&bytes)?;
+            db.inner
+                .insert(*key, rkyv::deserialize::<Node, rancor::Error>(archived)?);
+        }
+        db.reserve_keys();
+        db.rebuild_indexes();
+        Ok(db)
+    }
+}
+
+/// whether `bytes` start like an indexed file, whatever state the rest is in
+pub(crate) fn is_indexed(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// reads every node of an indexed file in `bytes` that is still whole, the rest go in `lost`.
+/// only fails if the header can't be read, an index cut short loses the nodes it doesn't
+/// reach
+pub(crate) fn salvage(
+    mut bytes: &[u8],
+    lost: &mut Vec<SalvageError>,
+) -> Result<HashMap<Key, Node>, LoadError> {
+    let mut magic = [0; 8];
+    bytes.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(LoadError::NotIndexed);
+    }
+    let version = read_u32(&mut bytes)?;
+    if version != VERSION {
+        return Err(LoadError::Version(version));
+    }
+    let count = read_u64(&mut bytes)?;
+    let mut index: Vec<(Key, Location)> = Vec::new();
+    while (index.len() as u64) < count && bytes.len() as u64 >= ENTRY {
+        let key = Key(read_u64(&mut bytes)?);
+        let offset = read_u64(&mut bytes)?;
+        let len = read_u64(&mut bytes)?;
+        index.push((key, Location { offset, len }));
+    }
+    if (index.len() as u64) < count {
+        lost.push(SalvageError::Index {
+            missing: count - index.len() as u64,
+        });
+    }
+    // a whole index is followed by the nodes, a cut short one by nothing
+    let nodes_region = bytes;
+    let mut nodes = HashMap::with_capacity(index.len());
+    let mut node_bytes = AlignedVec::<16>::new();
+    for (key, location) in index {
+        let node = usize::try_from(location.offset)
+            .ok()
+            .zip(usize::try_from(location.len).ok())
+            .and_then(|(offset, len)| nodes_region.get(offset..offset.checked_add(len)?))
+            .ok_or_else(|| LoadError::from(io::Error::from(io::ErrorKind::UnexpectedEof)))
+            .and_then(|slice| {
+                node_bytes.clear();
+                node_bytes.extend_from_slice(slice);
+                let archived = rkyv::access::<ArchivedNode, rancor::Error>(&node_bytes)?;
+                Ok(rkyv::deserialize::<Node, rancor::Error>(archived)?)
+            });
+        match node {
+            Ok(node) => {
+                nodes.insert(key, node);
+            }
+            Err(source) => lost.push(SalvageError::Node { key, source }),
+        }
+    }
+    Ok(nodes)
+}
+
+impl<S: BuildHasher> Database<S> {
+    /// writes the database in the indexed format `LazyDatabase` opens, values are always
+    /// stored inline and soft removed nodes are left out. this is a separate file from the
+    /// one `save` writes, replaced the same way so a failed save leaves the previous file
+    pub fn save_indexed(&self, path: &Path) -> Result<(), SaveError> {
+        let stored = blob::externalize(&self.inner, None, false)?;
+        let mut keys: Vec<&Key> = stored
+            .keys()
+            .filter(|key| !self.inner[*key].is_deleted())
+            .collect();
+        keys.sort_unstable();
+        let nodes = keys
+            .iter()
+            .map(|key| rkyv::to_bytes::<rancor::Error>(&stored[*key]))
+            .collect::<Result<Vec<_>, _>>()?;
+        let temporary = suffixed(path, ".tmp");
+        let file = File::create(&temporary)?;
+        let mut w = BufWriter::new(&file);
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&(keys.len() as u64).to_le_bytes())?;
+        let mut offset: u64 = 0;
+        for (key, node) in keys.iter().zip(&nodes) {
+            let len = node.len() as u64;
+            w.write_all(&key.0.to_le_bytes())?;
+            w.write_all(&offset.to_le_bytes())?;
+            w.write_all(&len.to_le_bytes())?;
+            offset += len;
+        }
+        for node in &nodes {
+            w.write_all(node)?;
+        }
+        w.flush()?;
+        drop(w);
+        if self.durable {
+            file.sync_all()?;
+        }
+        drop(file);
+        fs::rename(&temporary, path)?;
+        if self.durable {
+            sync_dir(parent(path))?;
+        }
+        Ok(())
+    }
+}
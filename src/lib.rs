@@ -1,38 +1,153 @@
 use std::{
-    collections::{HashMap, HashSet},
-    fmt::Display,
-    fs::{self, OpenOptions},
-    io::Write,
+    any::{TypeId, type_name},
+    collections::{HashMap, HashSet, VecDeque, hash_map},
+    fmt::{Debug, Display},
+    fs::{self, File, OpenOptions},
+    hash::{BuildHasher, BuildHasherDefault},
+    io::{self, Read, Write},
     num::ParseIntError,
-    path::PathBuf,
-    sync::LazyLock,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, OnceLock, PoisonError,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use rkyv::{
     Portable,
-    api::high::{HighSerializer, HighValidator},
+    api::high::{HighDeserializer, HighSerializer, HighValidator},
     bytecheck::CheckBytes,
-    collections::swiss_table::ArchivedHashMap,
-    ser::allocator::ArenaHandle,
+    ser::allocator::{Arena, ArenaHandle},
     util::AlignedVec,
+    vec::ArchivedVec,
+    with::{AsVec, Identity, MapKV, Skip},
 };
+use rustc_hash::FxHasher;
 use thiserror::Error;
 
+mod analytics;
+mod blob;
+mod builder;
+mod export;
+mod import;
+mod keys;
+mod kinds;
+mod lazy;
+mod multi;
+mod paths;
+mod query;
+mod salvage;
+mod shared;
+mod snapshot;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod walk;
+
+use snapshot::Contents;
+
+pub use builder::DatabaseBuilder;
+pub use import::ImportError;
+#[cfg(feature = "rand")]
+pub use keys::RandomKeys;
+pub use keys::{CounterKeys, KeyGenerator, TimestampKeys};
+pub use kinds::KindMarker;
+pub use lazy::LazyDatabase;
+pub use multi::MultiDatabase;
+pub use query::Query;
+pub use salvage::SalvageError;
+pub use shared::{SharedDatabase, Snapshot};
+pub use walk::{Control, Order, Visitor};
+
 /// key struct that is only gien out by the database to prevent non-existent keys
 #[derive(
-    Debug, Clone, Copy, PartialEq, Eq, Hash, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[rkyv(
+    compare(PartialEq),
+    derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord)
 )]
-#[rkyv(compare(PartialEq), derive(Debug, Hash, PartialEq, Eq))]
 pub struct Key(u64);
 
 #[derive(Error, Debug)]
 #[error("invalid key")]
 pub struct KeyParseError(#[from] ParseIntError);
 
+#[derive(Error, Debug)]
+#[error("{0} already exists")]
+pub struct KeyExists(Key);
+
+#[derive(Error, Debug)]
+#[error("ran out of keys")]
+pub struct KeysExhausted;
+
+/// see `Database::set_max_nodes`
+#[derive(Error, Debug, Clone, Copy)]
+#[error("database is full at {max} nodes")]
+pub struct CapacityExceeded {
+    pub max: usize,
+}
+
+#[derive(Error, Debug)]
+pub enum CreateError {
+    #[error(transparent)]
+    Invalid(#[from] InvalidValue),
+    #[error(transparent)]
+    Exists(#[from] KeyExists),
+    #[error(transparent)]
+    OutOfKeys(#[from] KeysExhausted),
+    #[error(transparent)]
+    Full(#[from] CapacityExceeded),
+}
+
+#[derive(Error, Debug)]
+pub enum RemapError {
+    #[error("{0} doesn't exist")]
+    Missing(Key),
+    #[error(transparent)]
+    Exists(#[from] KeyExists),
+}
+
+/// the last key `Key::generate` handed out, databases keep their own in `TimestampKeys`
+static LAST_KEY: AtomicU64 = AtomicU64::new(0);
+
 impl Key {
+    /// panics once the key `u64::MAX` has been handed out, see `try_generate`
     pub fn generate() -> Self {
-        Self(u64::try_from(Utc::now().timestamp_nanos_opt().unwrap()).unwrap())
+        Self::try_generate().unwrap()
+    }
+    /// fails once the key `u64::MAX` has been handed out, there's nothing left after it
+    pub fn try_generate() -> Result<Self, KeysExhausted> {
+        Self::timestamp_after(&LAST_KEY).ok_or(KeysExhausted)
+    }
+    /// nanoseconds since the epoch, or one after `last` if that's later, and moves `last` up
+    /// to it. `None` once `last` is `u64::MAX`
+    fn timestamp_after(last: &AtomicU64) -> Option<Self> {
+        let now = Self::now();
+        let next = |last: u64| last.checked_add(1).map(|after| now.max(after));
+        let last = last
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, next)
+            .ok()?;
+        Some(Self(next(last).unwrap()))
+    }
+    fn now() -> u64 {
+        // a clock outside 1970..2262 doesn't fit, keys just count up from the last one then
+        Utc::now()
+            .timestamp_nanos_opt()
+            .and_then(|nanos| u64::try_from(nanos).ok())
+            .unwrap_or(0)
     }
     pub fn parse(key: &str) -> Result<Self, KeyParseError> {
         Ok(Self(key.parse()?))
@@ -45,202 +160,2487 @@ impl Display for Key {
     }
 }
 
-#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
-#[rkyv(compare(PartialEq), derive(Debug))]
-pub struct Value(Vec<u8>);
+/// the type the value bytes were first validated as, so repeated reads can skip bytecheck,
+/// values are almost always read back as a single type so one slot is enough
+#[derive(Debug, Clone, Default)]
+struct Validated(OnceLock<TypeId>);
+
+impl Validated {
+    fn contains(&self, id: TypeId) -> bool {
+        self.0.get() == Some(&id)
+    }
+    fn insert(&self, id: TypeId) {
+        let _ = self.0.set(id);
+    }
+}
+
+/// decompressed bytes of a compressed value, filled in the first time the value is read
+#[derive(Debug, Clone, Default)]
+struct Decompressed(OnceLock<AlignedVec>);
+
+/// blob a save wrote the value to, so later saves don't have to check the file again
+#[derive(Debug, Clone, Default)]
+struct Written(OnceLock<String>);
+
+/// hash of the name of the archived type a value was serialized as, FNV-1a because the std
+/// hasher isn't guaranteed to stay the same between releases and tags get saved to disk.
+/// type names can still change between compiler versions, which shows up as a mismatch,
+/// `Value::deserialize_untagged` reads those anyway
+fn type_tag<T: ?Sized>() -> u64 {
+    fnv1a(type_name::<T>().as_bytes())
+}
+
+/// 64 bit FNV-1a, for hashes that get saved and have to come out the same in every build
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[derive(Error, Debug)]
+pub enum DeserializeError {
+    #[error("value was serialized as a different type")]
+    TypeMismatch,
+    #[error("invalid value")]
+    Invalid(#[from] rancor::Error),
+    #[error("failed to decompress the value")]
+    Decompress(#[from] io::Error),
+}
+
+#[derive(Error, Debug)]
+#[error("value is {size} bytes, over the limit of {max}")]
+pub struct ValueTooLarge {
+    pub size: usize,
+    pub max: usize,
+}
+
+#[derive(Error, Debug)]
+pub enum InvalidValue {
+    #[error(transparent)]
+    TooLarge(#[from] ValueTooLarge),
+    #[error("value rejected: {0}")]
+    Rejected(String),
+}
+
+type ValidatorFn = dyn Fn(&Value) -> Result<(), String> + Send + Sync;
+
+/// what `Database::set_validator` takes, behind an `Arc` so the database stays `Clone`
+#[derive(Clone)]
+struct Validator(Arc<ValidatorFn>);
+
+impl Debug for Validator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Validator")
+    }
+}
+
+/// what a value has to pass before it's stored
+#[derive(Debug, Clone, Default)]
+struct Checks {
+    /// see `Database::set_max_value_bytes`
+    max_value_bytes: Option<usize>,
+    validator: Option<Validator>,
+    /// threshold and level, see `Database::set_value_compression`
+    #[cfg(feature = "zstd")]
+    compression: Option<(usize, i32)>,
+}
+
+impl Checks {
+    /// also compresses the value once it passed, if it's compressed at all
+    fn check(&self, value: &mut Value) -> Result<(), InvalidValue> {
+        value.fits(self.max_value_bytes)?;
+        if let Some(Validator(validator)) = &self.validator {
+            validator(value).map_err(InvalidValue::Rejected)?;
+        }
+        #[cfg(feature = "zstd")]
+        if let Some((threshold, level)) = self.compression
+            && value.len() > threshold
+        {
+            value.compress(level);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct Value {
+    bytes: Vec<u8>,
+    /// `type_tag` of the archived type, `None` for values that were made from raw bytes
+    tag: Option<u64>,
+    /// name of the sidecar file the bytes are saved to when they're stored out of line,
+    /// in memory `bytes` are always loaded
+    blob: Option<String>,
+    /// whether `bytes` are zstd compressed, see `Database::set_value_compression`
+    compressed: bool,
+    #[rkyv(with = Skip)]
+    validated: Validated,
+    #[rkyv(with = Skip)]
+    decompressed: Decompressed,
+    #[rkyv(with = Skip)]
+    written: Written,
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl Eq for Value {}
+
+impl PartialEq<Value> for ArchivedValue {
+    fn eq(&self, other: &Value) -> bool {
+        self.bytes == other.bytes
+    }
+}
 
 impl Value {
-    pub fn serialize(
-        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
-    ) -> Self {
-        Self(rkyv::to_bytes::<rancor::Error>(value).unwrap().into_vec())
+    /// wraps bytes that are already rkyv serialized, they aren't checked until they're read
+    /// and since there's no type tag they read back as any type they happen to validate as
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            tag: None,
+            blob: None,
+            compressed: false,
+            validated: Validated::default(),
+            decompressed: Decompressed::default(),
+            written: Written::default(),
+        }
+    }
+    fn tagged<T: rkyv::Archive + ?Sized>(bytes: Vec<u8>) -> Self {
+        Self {
+            tag: Some(type_tag::<T::Archived>()),
+            ..Self::from_bytes(bytes)
+        }
+    }
+    pub fn serialize<T>(value: &T) -> Self
+    where
+        T: for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    {
+        Self::tagged::<T>(rkyv::to_bytes::<rancor::Error>(value).unwrap().into_vec())
+    }
+    /// `false` when the value is tagged with a type other than `T`, untagged values could be anything
+    fn is<T: ?Sized>(&self) -> bool {
+        self.tag.is_none_or(|tag| tag == type_tag::<T>())
     }
     pub fn deserialize<T: Portable + for<'a> CheckBytes<HighValidator<'a, rancor::Error>>>(
         &self,
     ) -> Option<&T> {
-        rkyv::access::<T, _>(&self.0).ok()
+        self.try_deserialize().ok()
+    }
+    /// `deserialize` without checking the type tag, for values whose archived type got a
+    /// different name, like after moving it to another module or upgrading the compiler. the
+    /// bytes are still validated as `T`
+    pub fn deserialize_untagged<
+        T: Portable + for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    >(
+        &self,
+    ) -> Option<&T> {
+        rkyv::access::<T, rancor::Error>(self.as_bytes().ok()?).ok()
+    }
+    /// same as `deserialize` but tells a value of the wrong type apart from corrupt bytes
+    pub fn try_deserialize<T: Portable + for<'a> CheckBytes<HighValidator<'a, rancor::Error>>>(
+        &self,
+    ) -> Result<&T, DeserializeError> {
+        if !self.is::<T>() {
+            return Err(DeserializeError::TypeMismatch);
+        }
+        Ok(rkyv::access::<T, _>(self.as_bytes()?)?)
+    }
+    /// the rkyv bytes, compressed values get decompressed the first time and kept around.
+    /// `from_bytes` and `Database::set_value_bytes` take them back but without the type tag,
+    /// clone the value to keep it
+    pub fn as_bytes(&self) -> io::Result<&[u8]> {
+        if !self.compressed {
+            return Ok(&self.bytes);
+        }
+        if let Some(bytes) = self.decompressed.0.get() {
+            return Ok(bytes);
+        }
+        #[cfg(feature = "zstd")]
+        {
+            let mut bytes = AlignedVec::new();
+            bytes.extend_from_reader(&mut zstd::Decoder::new(self.bytes.as_slice())?)?;
+            // another thread might have got there first, both decompressed the same bytes
+            let _ = self.decompressed.0.set(bytes);
+            Ok(self.decompressed.0.get().unwrap())
+        }
+        #[cfg(not(feature = "zstd"))]
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the value is compressed and the zstd feature is off",
+        ))
+    }
+    /// replaces the bytes with zstd compressed ones at `level`, unless that doesn't make
+    /// them any smaller
+    #[cfg(feature = "zstd")]
+    fn compress(&mut self, level: i32) {
+        if self.compressed {
+            return;
+        }
+        if let Ok(compressed) = zstd::bulk::compress(&self.bytes, level)
+            && compressed.len() < self.bytes.len()
+        {
+            self.bytes = compressed;
+            self.compressed = true;
+        }
+    }
+    /// owned copy of the value instead of a reference to the archived one
+    pub fn deserialize_owned<T>(&self) -> Option<T>
+    where
+        T: rkyv::Archive,
+        T::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>
+            + rkyv::Deserialize<T, HighDeserializer<rancor::Error>>,
+    {
+        let archived = self.deserialize::<T::Archived>()?;
+        rkyv::deserialize::<T, rancor::Error>(archived).ok()
+    }
+    /// `deserialize` for values that were serialized from a `Vec<T>`, saves spelling out the
+    /// archived type. arrays archive inline rather than as a vec so a `[T; N]` value still has
+    /// to be read with `deserialize::<[T::Archived; N]>`
+    pub fn deserialize_vec<T: rkyv::Archive>(&self) -> Option<&ArchivedVec<T::Archived>>
+    where
+        ArchivedVec<T::Archived>: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    {
+        self.deserialize::<ArchivedVec<T::Archived>>()
+    }
+    /// same as `deserialize` but only validates the bytes the first time a given `T` is requested,
+    /// for hot nodes that get read over and over
+    pub fn deserialize_cached<
+        T: Portable + 'static + for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    >(
+        &self,
+    ) -> Option<&T> {
+        let id = TypeId::of::<T>();
+        if self.validated.contains(id) {
+            let bytes = self.as_bytes().ok()?;
+            // SAFETY: the bytes were already validated as `T` and a value's bytes never change,
+            // `update` swaps in a whole new value with an empty cache
+            return Some(unsafe { rkyv::access_unchecked::<T>(bytes) });
+        }
+        let value = self.deserialize::<T>()?;
+        self.validated.insert(id);
+        Some(value)
+    }
+    /// same as `deserialize` but for an archive that lives in `range` of the value bytes,
+    /// out of range slices return `None`
+    pub fn deserialize_slice<T: Portable + for<'a> CheckBytes<HighValidator<'a, rancor::Error>>>(
+        &self,
+        range: Range<usize>,
+    ) -> Option<&T> {
+        let bytes = self.as_bytes().ok()?.get(range)?;
+        rkyv::access::<T, _>(bytes).ok()
+    }
+    fn fits(&self, max: Option<usize>) -> Result<(), ValueTooLarge> {
+        match max {
+            Some(max) if self.len() > max => Err(ValueTooLarge {
+                size: self.len(),
+                max,
+            }),
+            _ => Ok(()),
+        }
     }
     pub fn is_empty(&self) -> bool {
-        self.0.len() == 0
+        self.bytes.len() == 0
     }
+    /// how many bytes are stored, after compression for compressed values
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.bytes.len()
     }
 }
 
-static EMPTY_HASHSET: LazyLock<HashSet<Key>> = LazyLock::new(HashSet::new);
+/// scratch space for serializing a lot of values in a row, `Value::serialize` starts from an
+/// empty buffer every time and regrows it for each value
+#[derive(Default)]
+pub struct Serializer {
+    buffer: AlignedVec,
+    arena: Arena,
+}
+
+impl Serializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn serialize<T>(&mut self, value: &T) -> Value
+    where
+        T: for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        let buffer = rkyv::api::high::to_bytes_in_with_alloc::<_, _, rancor::Error>(
+            value,
+            buffer,
+            self.arena.acquire(),
+        )
+        .unwrap();
+        let value = Value::tagged::<T>(buffer.to_vec());
+        self.buffer = buffer;
+        value
+    }
+}
+
+/// keys are close to sequential so the default hasher is a fast one that doesn't try to
+/// resist collision attacks
+pub type NodeHasher = BuildHasherDefault<FxHasher>;
 
-/// TODO: it's possible to connect to the same node more than once with different kinds
+/// the keys a node is connected to under one kind. with the `sorted-connections` feature
+/// it's a `BTreeSet` instead, inserts get slower but iterating the connections of a node
+/// goes in key order every time. nodes and kinds still iterate in whatever order their maps
+/// are in
 ///
-/// TODO: it's possible to a node to connect to itself
-#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+/// the feature isn't additive: code that calls `HashSet` only methods on a `KeySet`, like
+/// `hasher` or `shrink_to_fit`, stops compiling once anything in the build turns it on. stick
+/// to what both sets have, `contains`, `len`, `iter` and the like, in libraries. files are
+/// the same either way since sets are saved as lists of keys
+#[cfg(not(feature = "sorted-connections"))]
+pub type KeySet = HashSet<Key, NodeHasher>;
+#[cfg(feature = "sorted-connections")]
+pub type KeySet = std::collections::BTreeSet<Key>;
+
+#[cfg(not(feature = "sorted-connections"))]
+static EMPTY_KEYSET: KeySet = HashSet::with_hasher(NodeHasher::new());
+#[cfg(feature = "sorted-connections")]
+static EMPTY_KEYSET: KeySet = KeySet::new();
+
+/// how the kind to `KeySet` maps of a node are archived, as lists so the format doesn't
+/// depend on `sorted-connections`
+type AsSets = MapKV<Identity, AsVec>;
+
+/// a node can connect to the same node under more than one kind, see `connections_to` and
+/// `Database::set_unique_targets`, but never to itself
+#[derive(Debug, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(derive(Debug))]
 pub struct Node {
     value: Value,
-    connections: HashMap<String, HashSet<Key>>,
+    #[rkyv(with = AsSets)]
+    connections: HashMap<String, KeySet>,
+    /// nodes connected to this one under a `Directionality::Directed` kind, they show up here
+    /// instead of in `connections` so removing this node can still clean them up
+    #[rkyv(with = AsSets)]
+    incoming: HashMap<String, KeySet>,
+    /// microseconds since the epoch each connection was made at, only kept while
+    /// `Database::set_edge_timestamps` is on
+    connected_at: HashMap<String, HashMap<Key, i64>>,
+    /// one way connections the other node doesn't know about, see `Database::connect_weak`
+    #[rkyv(with = AsSets)]
+    weak: HashMap<String, KeySet>,
+    /// microseconds since the epoch of the last `Database::touch`
+    touched_at: Option<i64>,
+    /// set while the node is soft removed, see `Database::soft_remove`
+    tombstone: Option<Tombstone>,
+}
+
+/// connections that neighbors of a soft removed node had to it, taken out of the neighbors
+/// so live nodes never point at removed ones and put back by `Database::restore`
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+struct Tombstone {
+    connections: Vec<(Key, String)>,
+    incoming: Vec<(Key, String)>,
+}
+
+impl Tombstone {
+    fn add(&mut self, neighbor: Key, connections: Vec<String>, incoming: Vec<String>) {
+        let with_neighbor = |kind: String| (neighbor, kind);
+        self.connections
+            .extend(connections.into_iter().map(with_neighbor));
+        self.incoming
+            .extend(incoming.into_iter().map(with_neighbor));
+    }
 }
 
 impl Node {
     pub fn new(
         value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
     ) -> Self {
-        let value = Value::serialize(value);
+        Self::from_value(Value::serialize(value))
+    }
+    fn from_value(value: Value) -> Self {
         Self {
             value,
             connections: HashMap::new(),
+            incoming: HashMap::new(),
+            connected_at: HashMap::new(),
+            weak: HashMap::new(),
+            touched_at: None,
+            tombstone: None,
         }
     }
     pub fn destruct(self) -> (impl Iterator<Item = Key>, Value) {
         (
             self.connections
-                .into_iter()
-                .flat_map(|(_kind, nodes)| nodes.into_iter()),
+                .into_values()
+                .chain(self.incoming.into_values())
+                .flat_map(|nodes| nodes.into_iter()),
             self.value,
         )
     }
     pub fn remove_connection(&mut self, key: &Key) {
-        self.connections.iter_mut().for_each(|(_kind, nodes)| {
-            nodes.remove(key);
+        self.connections
+            .iter_mut()
+            .chain(self.incoming.iter_mut())
+            .for_each(|(_kind, nodes)| {
+                nodes.remove(key);
+            });
+        self.connected_at.values_mut().for_each(|times| {
+            times.remove(key);
         });
     }
+    fn remove_connections(&mut self, keys: &HashSet<Key>) {
+        self.connections
+            .iter_mut()
+            .chain(self.incoming.iter_mut())
+            .for_each(|(_kind, nodes)| {
+                nodes.retain(|key| !keys.contains(key));
+            });
+        self.connected_at
+            .values_mut()
+            .for_each(|times| times.retain(|key, _time| !keys.contains(key)));
+    }
+    /// points every connection to `old` at `new` instead
+    fn rename_connections(&mut self, old: &Key, new: Key) {
+        for nodes in self
+            .connections
+            .values_mut()
+            .chain(self.incoming.values_mut())
+        {
+            if nodes.remove(old) {
+                nodes.insert(new);
+            }
+        }
+        for times in self.connected_at.values_mut() {
+            if let Some(time) = times.remove(old) {
+                times.insert(new, time);
+            }
+        }
+        if let Some(tombstone) = &mut self.tombstone {
+            tombstone
+                .connections
+                .iter_mut()
+                .chain(tombstone.incoming.iter_mut())
+                .filter(|(neighbor, _kind)| neighbor == old)
+                .for_each(|(neighbor, _kind)| *neighbor = new);
+        }
+    }
+    /// every node this one has a connection to or from
+    fn neighbors(&self) -> HashSet<Key> {
+        self.connections
+            .values()
+            .chain(self.incoming.values())
+            .flatten()
+            .copied()
+            .collect()
+    }
+    /// takes over every connection of `other` except the ones to `key`, which is this node
+    fn absorb(&mut self, key: &Key, other: Node) {
+        let merge = |into: &mut HashMap<String, KeySet>, from: HashMap<String, KeySet>| {
+            for (kind, nodes) in from {
+                into.entry(kind)
+                    .or_default()
+                    .extend(nodes.into_iter().filter(|node| node != key));
+            }
+        };
+        merge(&mut self.connections, other.connections);
+        merge(&mut self.incoming, other.incoming);
+        merge(&mut self.weak, other.weak);
+        for (kind, times) in other.connected_at {
+            self.connected_at
+                .entry(kind)
+                .or_default()
+                .extend(times.into_iter().filter(|(node, _time)| node != key));
+        }
+    }
+    /// points every connection at the key `keys` has for it, connections to keys it doesn't
+    /// have are dropped
+    fn remap(&mut self, keys: &HashMap<Key, Key>) {
+        for nodes in self
+            .connections
+            .values_mut()
+            .chain(self.incoming.values_mut())
+            .chain(self.weak.values_mut())
+        {
+            *nodes = nodes
+                .iter()
+                .filter_map(|key| keys.get(key).copied())
+                .collect();
+        }
+        for times in self.connected_at.values_mut() {
+            *times = times
+                .iter()
+                .filter_map(|(key, time)| Some((*keys.get(key)?, *time)))
+                .collect();
+        }
+    }
+    /// points every weak connection to `old` at `new` instead
+    fn rename_weak(&mut self, old: &Key, new: Key) {
+        for nodes in self.weak.values_mut() {
+            if nodes.remove(old) {
+                nodes.insert(new);
+            }
+        }
+    }
+    /// remembers when the connection to `key` under `kind` was made, `None` forgets it
+    fn stamp(&mut self, kind: &str, key: Key, at: Option<DateTime<Utc>>) {
+        match at {
+            Some(at) => {
+                self.connected_at
+                    .entry(kind.to_owned())
+                    .or_default()
+                    .insert(key, at.timestamp_micros());
+            }
+            None => {
+                if let Some(times) = self.connected_at.get_mut(kind) {
+                    times.remove(&key);
+                }
+            }
+        }
+    }
+    /// when the connection to `key` under `kind` was made, if it exists and was timestamped
+    pub fn connected_at(&self, kind: &str, key: &Key) -> Option<DateTime<Utc>> {
+        if !self.get_connections(kind).contains(key) {
+            return None;
+        }
+        let micros = *self.connected_at.get(kind)?.get(key)?;
+        DateTime::from_timestamp_micros(micros)
+    }
     pub fn connect(&mut self, kind: String, key: Key) {
         if let Some(nodes) = self.connections.get_mut(&kind) {
             nodes.insert(key);
         } else {
-            let nodes = HashSet::from([key]);
+            let nodes = KeySet::from_iter([key]);
             self.connections.insert(kind, nodes);
         }
     }
+    fn connect_incoming(&mut self, kind: String, key: Key) {
+        self.incoming.entry(kind).or_default().insert(key);
+    }
     pub fn connections(&self) -> impl Iterator<Item = (&str, usize)> {
         self.connections.iter().filter_map(|(kind, connections)| {
             (!connections.is_empty()).then_some((kind.as_str(), connections.len()))
         })
     }
-    pub fn get_connections(&self, kind: &str) -> &HashSet<Key> {
-        self.connections.get(kind).unwrap_or(&EMPTY_HASHSET)
+    /// every `(kind, target)` connection of this node
+    pub fn edges(&self) -> impl Iterator<Item = (&str, &Key)> {
+        self.connections
+            .iter()
+            .flat_map(|(kind, targets)| targets.iter().map(move |target| (kind.as_str(), target)))
+    }
+    /// number of connections across all kinds, a node connected under two kinds counts twice
+    pub fn total_degree(&self) -> usize {
+        self.connections.values().map(KeySet::len).sum()
+    }
+    pub fn is_isolated(&self) -> bool {
+        self.connections.values().all(KeySet::is_empty)
+    }
+    pub fn get_connections(&self, kind: &str) -> &KeySet {
+        self.connections.get(kind).unwrap_or(&EMPTY_KEYSET)
+    }
+    /// every kind this node connects to `target` under
+    pub fn connections_to(&self, target: &Key) -> Vec<&str> {
+        self.connections
+            .iter()
+            .filter(|(_kind, nodes)| nodes.contains(target))
+            .map(|(kind, _nodes)| kind.as_str())
+            .collect()
+    }
+    /// whether this node connects to `target` under any kind but `kind`
+    fn connects_otherwise(&self, target: &Key, kind: &str) -> bool {
+        self.connections
+            .iter()
+            .any(|(other, nodes)| other != kind && nodes.contains(target))
+    }
+    pub fn is_deleted(&self) -> bool {
+        self.tombstone.is_some()
+    }
+    /// takes `key` out of every connection of this node, returns the kinds it was under in
+    /// `connections` and in `incoming`
+    fn take_connections_to(&mut self, key: &Key) -> (Vec<String>, Vec<String>) {
+        let take = |connections: &mut HashMap<String, KeySet>| -> Vec<String> {
+            connections
+                .iter_mut()
+                .filter_map(|(kind, nodes)| nodes.remove(key).then(|| kind.clone()))
+                .collect()
+        };
+        (take(&mut self.connections), take(&mut self.incoming))
+    }
+    /// when `Database::touch` was last called on this node, `None` if it never was
+    pub fn touched_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_micros(self.touched_at?)
+    }
+    /// weak connections under `kind`, including ones to nodes that are gone since they're
+    /// only pruned by `Database::prune_weak`
+    pub fn get_weak(&self, kind: &str) -> &KeySet {
+        self.weak.get(kind).unwrap_or(&EMPTY_KEYSET)
+    }
+    /// nodes that connect to this one under a directed `kind`
+    pub fn get_incoming(&self, kind: &str) -> &KeySet {
+        self.incoming.get(kind).unwrap_or(&EMPTY_KEYSET)
     }
     pub fn value(&self) -> &Value {
         &self.value
     }
+    /// drops kinds that have no connections left and releases spare capacity
+    fn shrink_to_fit(&mut self) {
+        let connections = &self.connections;
+        self.connected_at.retain(|kind, times| {
+            let nodes = connections.get(kind).unwrap_or(&EMPTY_KEYSET);
+            times.retain(|key, _time| nodes.contains(key));
+            !times.is_empty()
+        });
+        self.connected_at.shrink_to_fit();
+        self.weak.retain(|_kind, nodes| !nodes.is_empty());
+        self.weak.shrink_to_fit();
+        for sets in [&mut self.connections, &mut self.incoming] {
+            sets.retain(|_kind, nodes| !nodes.is_empty());
+            #[cfg(not(feature = "sorted-connections"))]
+            sets.values_mut().for_each(HashSet::shrink_to_fit);
+            sets.shrink_to_fit();
+        }
+    }
 }
 
-#[derive(Debug)]
+/// what `Database::load_compressed` takes when there's no reason to pick a level
+#[cfg(feature = "zstd")]
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+
+/// somewhere to keep the database that the crate doesn't know about, like S3 or Redis. it
+/// gets the same bytes `Database::save_to_writer` writes, values are always stored inline
+pub trait StorageBackend: Debug + Send + Sync {
+    fn save(&self, bytes: &[u8]) -> io::Result<()>;
+    /// `None` if nothing was saved yet
+    fn load(&self) -> io::Result<Option<Vec<u8>>>;
+}
+
+/// where a database is kept, `Custom` takes any `StorageBackend`. the files aren't backends
+/// themselves because a backend only gets the finished snapshot, and a file needs more than
+/// that around it: values over `Database::set_blob_threshold` are written to blobs next to
+/// it first, `set_durable` syncs it and its directory and `set_backups` rotates the older
+/// files, all going by its path. `verify` and `load_salvage` read the file directly as well
+#[derive(Debug, Clone)]
 pub enum Storage {
     Memory,
     File(PathBuf),
+    /// see `Database::load_backend`
+    Custom(Arc<dyn StorageBackend>),
+    /// the database file is zstd compressed at `level`, from 1 for the fastest saves to 22
+    /// for the smallest files. saving 100k string nodes with 200k connections took 0.21s
+    /// for 5.5MB at 1, 0.26s for 5.0MB at 3, 0.7s for 4.4MB at 9 and 16s for 3.8MB at 19,
+    /// `cargo bench --features zstd -- compression` to measure it again
+    #[cfg(feature = "zstd")]
+    CompressedFile {
+        path: PathBuf,
+        level: i32,
+    },
 }
 
 impl Storage {
-    fn save(&self, data: &HashMap<Key, Node>) {
-        if let Self::File(path) = self {
-            let mut file = OpenOptions::new()
-                .create(true)
-                .truncate(true)
-                .write(true)
-                .open(path)
-                .unwrap();
-            let bytes = rkyv::to_bytes::<rancor::Error>(data).unwrap();
-            file.write_all(&bytes).unwrap();
+    fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Memory | Self::Custom(_) => None,
+            Self::File(path) => Some(path),
+            #[cfg(feature = "zstd")]
+            Self::CompressedFile { path, .. } => Some(path),
+        }
+    }
+    /// creates an empty database file if there isn't one yet,
+    /// also returns how many bytes the snapshot was
+    fn load<S: BuildHasher + Default>(&self) -> Result<(HashMap<Key, Node, S>, usize), LoadError> {
+        if let Self::Custom(backend) = self {
+            let Some(bytes) = backend.load()? else {
+                return Ok((HashMap::default(), 0));
+            };
+            return snapshot::read(&mut bytes.as_slice());
+        }
+        let Some(path) = self.path() else {
+            return Ok((HashMap::default(), 0));
+        };
+        if !path.is_file() {
+            OpenOptions::new().create_new(true).write(true).open(path)?;
+            return Ok((HashMap::default(), 0));
+        }
+        let mut file = File::open(path)?;
+        // a created but never saved file isn't a valid zstd frame
+        if file.metadata()?.len() == 0 {
+            return Ok((HashMap::default(), 0));
+        }
+        let (mut inner, bytes) = match self {
+            #[cfg(feature = "zstd")]
+            Self::CompressedFile { .. } => snapshot::read(&mut zstd::Decoder::new(file)?)?,
+            _ => snapshot::read(&mut file)?,
+        };
+        blob::resolve(&mut inner, &blob::dir(path))?;
+        Ok((inner, bytes))
+    }
+    /// returns how many bytes went into the database file before compression, `None` for memory
+    fn save<S>(
+        &self,
+        data: &HashMap<Key, Node, S>,
+        blob_threshold: Option<usize>,
+        durable: bool,
+        backups: usize,
+    ) -> Result<Option<usize>, SaveError> {
+        if let Self::Custom(backend) = self {
+            let stored = blob::externalize(data, None, false)?;
+            let mut bytes = Vec::new();
+            let len = snapshot::write(&stored, &mut bytes)?;
+            backend.save(&bytes)?;
+            return Ok(Some(len));
         }
+        let Some(path) = self.path() else {
+            return Ok(None);
+        };
+        let dir = blob::dir(path);
+        let blobs = blob_threshold.map(|threshold| (dir.as_path(), threshold));
+        let stored = blob::externalize(data, blobs, durable)?;
+        // the blobs have to be there before a snapshot that refers to them is
+        if durable && blobs.is_some() {
+            sync_dir(&dir)?;
+        }
+        // written next to the database file and renamed over it, so a save that fails or
+        // crashes midway leaves the previous snapshot in place
+        let temporary = suffixed(path, ".tmp");
+        let mut file = File::create(&temporary)?;
+        let bytes = match self {
+            #[cfg(feature = "zstd")]
+            Self::CompressedFile { level, .. } => {
+                let mut encoder = zstd::Encoder::new(&mut file, *level)?;
+                let bytes = snapshot::write(&stored, &mut encoder)?;
+                encoder.finish()?;
+                bytes
+            }
+            _ => snapshot::write(&stored, &mut file)?,
+        };
+        if durable {
+            file.sync_all()?;
+        }
+        drop(file);
+        rotate(path, backups)?;
+        fs::rename(&temporary, path)?;
+        if durable {
+            sync_dir(parent(path))?;
+        }
+        blob::remove_unreferenced(&stored, &dir)?;
+        Ok(Some(bytes))
     }
 }
 
-#[derive(Debug)]
-pub struct Database {
-    inner: HashMap<Key, Node>,
-    storage: Storage,
+/// `db.grahh` with `suffix` on the end of the file name
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
 }
 
-impl Database {
-    pub fn create(
-        &mut self,
-        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
-    ) -> Key {
-        let key = Key::generate();
-        let node = Node::new(value);
-        // this should always be `None` because otherwise we're having key generator collisions
-        let previous: Option<Node> = self.inner.insert(key, node);
-        assert!(previous.is_none(), "we're having key generator collisions");
-        key
-    }
-    pub fn remove(&mut self, key: Key) -> Option<Value> {
-        let node = self.inner.remove(&key)?;
-        let (connections, value) = node.destruct();
-        for ref connected in connections {
-            self.inner
-                .get_mut(connected)
-                .unwrap()
-                .remove_connection(&key);
-        }
-        Some(value)
+/// the directory `path` is in, `db.grahh` on its own is in the current one
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
     }
-    pub fn connect(
-        &mut self,
-        first_key: Key,
-        first_kind: String,
-        second_key: Key,
-        second_kind: String,
-    ) -> bool {
-        let [Some(node1), Some(node2)] = self.inner.get_disjoint_mut([&first_key, &second_key])
-        else {
-            return false;
-        };
-        node1.connect(first_kind, second_key);
-        node2.connect(second_kind, first_key);
-        true
+}
+
+/// `File::sync_all` for a directory, so files created or renamed in it are still there
+/// after a power failure, syncing a file doesn't cover its entry in the directory. only
+/// unix can open a directory for this, elsewhere it does nothing. a directory that doesn't
+/// exist has nothing to sync
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    match File::open(dir) {
+        Ok(dir) => dir.sync_all()?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+        Err(error) => return Err(error),
     }
-    pub fn disconnect(&mut self, first_key: &Key, second_key: &Key) -> bool {
-        let [Some(node1), Some(node2)] = self.inner.get_disjoint_mut([first_key, second_key])
-        else {
-            return false;
-        };
-        node1.remove_connection(second_key);
-        node2.remove_connection(first_key);
-        true
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// shifts `db.grahh.1` to `db.grahh.2` and so on, dropping the oldest, then makes
+/// `db.grahh.1` a hard link to the current file, or a copy where links aren't supported.
+/// the current file is never moved, so a crash partway through loses at most a backup
+fn rotate(path: &Path, backups: usize) -> io::Result<()> {
+    if backups == 0 || !path.is_file() {
+        return Ok(());
     }
-    pub fn select(&self, key: &Key, kind: &str) -> &HashSet<Key> {
-        let Some(node) = self.inner.get(key) else {
-            return &EMPTY_HASHSET;
-        };
-        node.get_connections(kind)
+    let backup = |n: usize| suffixed(path, &format!(".{n}"));
+    for n in (1..backups).rev() {
+        match fs::rename(backup(n), backup(n + 1)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+            _ => {}
+        }
     }
-    pub fn get(&self, key: &Key) -> Option<&Node> {
-        self.inner.get(key)
+    let first = backup(1);
+    match fs::remove_file(&first) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+        _ => {}
     }
-    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Node)> {
-        self.inner.iter()
+    if fs::hard_link(path, &first).is_err() {
+        fs::copy(path, &first)?;
     }
-    pub fn load(path: PathBuf) -> Self {
-        let inner: HashMap<Key, Node> = {
-            if !path.is_file() {
-                let _ = OpenOptions::new()
-                    .create_new(true)
-                    .write(true)
-                    .open(&path)
-                    .unwrap();
-                HashMap::new()
-            } else {
-                let bytes: Vec<u8> = fs::read(&path).unwrap();
-                let archive: &ArchivedHashMap<ArchivedKey, ArchivedNode> = rkyv::access::<
-                    ArchivedHashMap<ArchivedKey, ArchivedNode>,
-                    rancor::Error,
-                >(&bytes)
-                .unwrap();
-                rkyv::deserialize::<_, rancor::Error>(archive).unwrap()
-            }
-        };
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+pub enum SaveError {
+    #[error("failed to serialize the database")]
+    Serialize(#[from] rancor::Error),
+    #[error("failed to write the database")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum LoadError {
+    #[error("failed to read the database")]
+    Io(#[from] io::Error),
+    #[error("invalid database")]
+    Invalid(#[from] rancor::Error),
+    #[error("value is stored out of line in blob {0}, which can't be read")]
+    Blob(String),
+    #[error("not an indexed database")]
+    NotIndexed,
+    #[error("indexed database has unknown version {0}")]
+    Version(u32),
+    #[error("index of the indexed database is out of order or points past the end")]
+    Index,
+    #[error("database file has unknown version {0}, it was saved by a newer release")]
+    SnapshotVersion(u32),
+}
+
+/// owned copy of a node read straight from a snapshot, for when it has to be changed.
+/// a value that was saved out of line only has the name of its blob, not its bytes
+pub fn to_owned_node(archived: &ArchivedNode) -> Node {
+    // an `ArchivedNode` is only reachable through an archive that was already validated
+    rkyv::deserialize::<Node, rancor::Error>(archived).unwrap()
+}
+
+/// `T` along with whether it was borrowed mutably since it was last saved, any mutable
+/// borrow counts whether or not it changed anything
+struct Tracked<T> {
+    value: T,
+    dirty: AtomicBool,
+}
+
+impl<T> Tracked<T> {
+    fn new(value: T) -> Self {
         Self {
-            inner,
-            storage: Storage::File(path),
+            value,
+            dirty: AtomicBool::new(false),
         }
     }
-    pub fn save(&self) {
-        self.storage.save(&self.inner);
+    fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Relaxed)
     }
-    pub fn in_memory() -> Self {
+    fn clean(&self) {
+        self.dirty.store(false, Ordering::Relaxed);
+    }
+}
+
+impl<T> std::ops::Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> std::ops::DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        *self.dirty.get_mut() = true;
+        &mut self.value
+    }
+}
+
+impl<T: Clone> Clone for Tracked<T> {
+    fn clone(&self) -> Self {
         Self {
-            inner: HashMap::new(),
-            storage: Storage::Memory,
+            value: self.value.clone(),
+            dirty: AtomicBool::new(self.is_dirty()),
         }
     }
 }
+
+impl<T: Debug> Debug for Tracked<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// what dropping a `Database` does, see `Database::set_save_on_drop`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SaveOnDrop {
+    /// nothing, anything that wasn't saved is lost
+    #[default]
+    Off,
+    /// saves and prints the error to stderr if it fails
+    Log,
+    /// saves and panics if it fails, unless the thread is already panicking since that would
+    /// abort, then it logs instead
+    Panic,
+}
+
+/// size of the snapshot and how long the whole save took, blobs aren't counted in `bytes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveStats {
+    pub bytes: usize,
+    pub duration: Duration,
+}
+
+/// size of the snapshot and how long the whole load took, blobs aren't counted in `bytes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadStats {
+    pub bytes: usize,
+    pub duration: Duration,
+}
+
+/// behind a mutex because saving only borrows the database
+#[derive(Debug, Default)]
+struct LastSave(Mutex<Option<SaveStats>>);
+
+impl LastSave {
+    fn get(&self) -> Option<SaveStats> {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    fn set(&self, stats: SaveStats) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(stats);
+    }
+}
+
+impl Clone for LastSave {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.get()))
+    }
+}
+
+/// clones start with `SaveOnDrop::Off`, otherwise a clone dropped after the original was
+/// saved would write its older nodes over the file
+#[derive(Debug, Default)]
+struct OnDrop(SaveOnDrop);
+
+impl Clone for OnDrop {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// how `Database::connect` treats a kind once it's declared with `Database::declare_kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Directionality {
+    /// `connect(a, kind, b, kind)` only connects `a` to `b`, `b` doesn't see `a` under `kind`
+    Directed,
+    /// both sides are always connected under this kind, connecting it to a different kind
+    /// on the other side fails
+    Undirected,
+}
+
+/// view into a single slot of the database, same idea as `hash_map::Entry`. a soft removed
+/// node still holds its slot, `try_or_insert` fails on it and `and_modify` leaves it alone
+#[derive(Debug)]
+pub struct Entry<'db> {
+    entry: hash_map::Entry<'db, Key, Node>,
+    checks: &'db Checks,
+    keys: &'db dyn KeyGenerator,
+    /// whether a vacant entry can be filled, see `Database::set_max_nodes`
+    room: Result<(), CapacityExceeded>,
+}
+
+impl<'db> Entry<'db> {
+    pub fn key(&self) -> &Key {
+        self.entry.key()
+    }
+    /// panics if the value doesn't pass the database's checks or the database is full, see
+    /// `try_or_insert`
+    pub fn or_insert(
+        self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> &'db mut Node {
+        self.try_or_insert(value).unwrap()
+    }
+    pub fn try_or_insert(
+        self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> Result<&'db mut Node, CreateError> {
+        match self.entry {
+            hash_map::Entry::Occupied(entry) if entry.get().is_deleted() => {
+                Err(KeyExists(*entry.key()).into())
+            }
+            hash_map::Entry::Occupied(entry) => Ok(entry.into_mut()),
+            hash_map::Entry::Vacant(entry) => {
+                self.room?;
+                let mut value = Value::serialize(value);
+                self.checks.check(&mut value)?;
+                self.keys.reserve(*entry.key());
+                Ok(entry.insert(Node::from_value(value)))
+            }
+        }
+    }
+    pub fn and_modify(self, f: impl FnOnce(&mut Node)) -> Self {
+        let entry = self.entry.and_modify(|node| {
+            if !node.is_deleted() {
+                f(node);
+            }
+        });
+        Self { entry, ..self }
+    }
+    /// replaces the value of a node that's there and not soft removed, checked the same as
+    /// `Database::update`. panics if the value doesn't pass the checks, see `try_and_update`
+    pub fn and_update(
+        self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> Self {
+        self.try_and_update(value).unwrap()
+    }
+    pub fn try_and_update(
+        mut self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> Result<Self, InvalidValue> {
+        if let hash_map::Entry::Occupied(entry) = &mut self.entry
+            && !entry.get().is_deleted()
+        {
+            let mut value = Value::serialize(value);
+            self.checks.check(&mut value)?;
+            entry.get_mut().value = value;
+        }
+        Ok(self)
+    }
+}
+
+/// `S` hashes the keys of the node map, see `NodeHasher`
+#[derive(Debug, Clone)]
+pub struct Database<S = NodeHasher> {
+    inner: Tracked<HashMap<Key, Node, S>>,
+    storage: Storage,
+    /// values longer than this are saved out of line, see `set_blob_threshold`
+    blob_threshold: Option<usize>,
+    /// values that don't pass are rejected, see `set_max_value_bytes` and `set_validator`
+    checks: Checks,
+    /// behind an `Arc` so the database stays `Clone`, see `set_key_generator`
+    keys: Arc<dyn KeyGenerator>,
+    kinds: HashMap<String, Directionality>,
+    /// see `set_max_nodes`
+    max_nodes: Option<usize>,
+    /// whether `connect` records when connections are made, see `set_edge_timestamps`
+    edge_timestamps: bool,
+    /// whether `connect` refuses a second kind between the same nodes, see `set_unique_targets`
+    unique_targets: bool,
+    /// whether `save` waits for the file to reach the disk, see `set_durable`
+    durable: bool,
+    /// what dropping the database does, see `set_save_on_drop`
+    save_on_drop: OnDrop,
+    /// how many previous database files `save` keeps, see `set_backups`
+    backups: usize,
+    last_save: LastSave,
+    last_load: Option<LoadStats>,
+}
+
+impl<S: BuildHasher> Database<S> {
+    /// panics if the value doesn't pass the checks or the database is full, see `try_create`
+    pub fn create(
+        &mut self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> Key {
+        self.try_create(value).unwrap()
+    }
+    pub fn try_create(
+        &mut self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> Result<Key, CreateError> {
+        let mut value = Value::serialize(value);
+        self.checks.check(&mut value)?;
+        self.insert_generated(Node::from_value(value))
+    }
+    /// a generator can land on a key that's already taken, like `RandomKeys` by chance or
+    /// `TimestampKeys` on one it didn't reserve, so it gets another try for every node before
+    /// giving up with `KeyExists`
+    fn insert_generated(&mut self, node: Node) -> Result<Key, CreateError> {
+        self.room(1)?;
+        let mut key = self.keys.generate().ok_or(KeysExhausted)?;
+        for _retry in 0..self.inner.len() {
+            if !self.inner.contains_key(&key) {
+                break;
+            }
+            key = self.keys.generate().ok_or(KeysExhausted)?;
+        }
+        let hash_map::Entry::Vacant(entry) = self.inner.entry(key) else {
+            return Err(KeyExists(key).into());
+        };
+        entry.insert(node);
+        Ok(key)
+    }
+    /// key of a node whose value `eq` matches, otherwise `create`s one with `value`.
+    /// checks every node so it's O(n)
+    pub fn get_or_create(
+        &mut self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+        eq: impl Fn(&Value) -> bool,
+    ) -> Key {
+        self.try_get_or_create(value, eq).unwrap()
+    }
+    pub fn try_get_or_create(
+        &mut self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+        eq: impl Fn(&Value) -> bool,
+    ) -> Result<Key, CreateError> {
+        let existing = self
+            .iter()
+            .find_map(|(key, node)| eq(node.value()).then_some(*key));
+        existing.map_or_else(|| self.try_create(value), Ok)
+    }
+    /// `create` that also hands back the new node, for connecting or reading it straight away
+    pub fn insert(
+        &mut self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> (Key, &mut Node) {
+        self.try_insert(value).unwrap()
+    }
+    pub fn try_insert(
+        &mut self,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> Result<(Key, &mut Node), CreateError> {
+        let key = self.try_create(value)?;
+        Ok((key, self.inner.get_mut(&key).unwrap()))
+    }
+    /// bulk version of `create` that reuses one serialization buffer for all the values
+    pub fn create_many<'v, T>(&mut self, values: impl IntoIterator<Item = &'v T>) -> Vec<Key>
+    where
+        T: for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>> + 'v,
+    {
+        self.try_create_many(values).unwrap()
+    }
+    /// every value is checked and there has to be room for all of them before any is created,
+    /// so an error means nothing was created
+    pub fn try_create_many<'v, T>(
+        &mut self,
+        values: impl IntoIterator<Item = &'v T>,
+    ) -> Result<Vec<Key>, CreateError>
+    where
+        T: for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>> + 'v,
+    {
+        let mut serializer = Serializer::new();
+        let mut values: Vec<Value> = values
+            .into_iter()
+            .map(|value| serializer.serialize(value))
+            .collect();
+        for value in &mut values {
+            self.checks.check(value)?;
+        }
+        self.room(values.len())?;
+        let mut keys: Vec<Key> = Vec::with_capacity(values.len());
+        for value in values {
+            match self.insert_generated(Node::from_value(value)) {
+                Ok(key) => keys.push(key),
+                Err(error) => {
+                    for key in &keys {
+                        self.inner.remove(key);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(keys)
+    }
+    /// panics if the value doesn't pass the checks or the database is full, see
+    /// `try_create_with_key`
+    pub fn create_with_key(
+        &mut self,
+        key: Key,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> Result<(), KeyExists> {
+        if self.inner.contains_key(&key) {
+            return Err(KeyExists(key));
+        }
+        self.try_create_with_key(key, value).unwrap();
+        Ok(())
+    }
+    pub fn try_create_with_key(
+        &mut self,
+        key: Key,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> Result<(), CreateError> {
+        self.room(1)?;
+        let hash_map::Entry::Vacant(entry) = self.inner.entry(key) else {
+            return Err(KeyExists(key).into());
+        };
+        let mut value = Value::serialize(value);
+        self.checks.check(&mut value)?;
+        self.keys.reserve(key);
+        entry.insert(Node::from_value(value));
+        Ok(())
+    }
+    /// panics if the value doesn't pass the checks, see `try_update`
+    pub fn update(
+        &mut self,
+        key: &Key,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> bool {
+        self.try_update(key, value).unwrap()
+    }
+    pub fn try_update(
+        &mut self,
+        key: &Key,
+        value: &impl for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    ) -> Result<bool, InvalidValue> {
+        if self.live(key).is_none() {
+            return Ok(false);
+        }
+        let mut value = Value::serialize(value);
+        self.checks.check(&mut value)?;
+        self.inner.get_mut(key).unwrap().value = value;
+        Ok(true)
+    }
+    /// appends `item` to a value stored as a `Vec<T>`, the whole vec still gets deserialized
+    /// and serialized again. false if the node is missing or its value isn't a `Vec<T>`,
+    /// panics if the new value doesn't pass the checks, see `try_push_to_value`
+    pub fn push_to_value<T>(&mut self, key: Key, item: &T) -> bool
+    where
+        T: rkyv::Archive + Clone,
+        Vec<T>: for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+        <Vec<T> as rkyv::Archive>::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>
+            + rkyv::Deserialize<Vec<T>, HighDeserializer<rancor::Error>>,
+    {
+        self.try_push_to_value(key, item).unwrap()
+    }
+    pub fn try_push_to_value<T>(&mut self, key: Key, item: &T) -> Result<bool, InvalidValue>
+    where
+        T: rkyv::Archive + Clone,
+        Vec<T>: for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+        <Vec<T> as rkyv::Archive>::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>
+            + rkyv::Deserialize<Vec<T>, HighDeserializer<rancor::Error>>,
+    {
+        let Some(node) = self.live(&key) else {
+            return Ok(false);
+        };
+        let Some(mut items) = node.value.deserialize_owned::<Vec<T>>() else {
+            return Ok(false);
+        };
+        items.push(item.clone());
+        self.try_update(&key, &items)
+    }
+    /// makes everything that adds nodes fail with `CapacityExceeded` once there are `max`
+    /// of them, soft removed ones included until `purge_deleted` since they still take up
+    /// memory. nothing is ever evicted to make room. `None` is no limit, which is the default
+    pub fn set_max_nodes(&mut self, max: Option<usize>) {
+        self.max_nodes = max;
+    }
+    /// how many nodes are stored, soft removed ones included, see `set_max_nodes`.
+    /// `iter().count()` for only the live ones
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+    /// whether `extra` more nodes fit under `set_max_nodes`
+    fn room(&self, extra: usize) -> Result<(), CapacityExceeded> {
+        match self.max_nodes {
+            Some(max) if self.inner.len() + extra > max => Err(CapacityExceeded { max }),
+            _ => Ok(()),
+        }
+    }
+    /// replaces the value with bytes that are already rkyv serialized, nothing about them is
+    /// checked, not even `max_value_bytes` or the validator. see `Value::from_bytes`
+    pub fn set_value_bytes(&mut self, key: Key, bytes: Vec<u8>) -> bool {
+        let Some(node) = self.live_mut(&key) else {
+            return false;
+        };
+        node.value = Value::from_bytes(bytes);
+        true
+    }
+    pub fn entry(&mut self, key: Key) -> Entry<'_> {
+        let room = self.room(1);
+        Entry {
+            entry: self.inner.entry(key),
+            checks: &self.checks,
+            keys: &*self.keys,
+            room,
+        }
+    }
+    /// values bigger than `max` bytes get rejected by `try_create` and `try_update`, the
+    /// other ways of creating or updating a value panic instead. `None` allows any size
+    pub fn set_max_value_bytes(&mut self, max: Option<usize>) {
+        self.checks.max_value_bytes = max;
+    }
+    /// every new or updated value has to pass `validator` first, rejected the same way as
+    /// values over `max_value_bytes`
+    pub fn set_validator(
+        &mut self,
+        validator: impl Fn(&Value) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.checks.validator = Some(Validator(Arc::new(validator)));
+    }
+    pub fn clear_validator(&mut self) {
+        self.checks.validator = None;
+    }
+    fn live(&self, key: &Key) -> Option<&Node> {
+        self.inner.get(key).filter(|node| !node.is_deleted())
+    }
+    fn live_mut(&mut self, key: &Key) -> Option<&mut Node> {
+        self.inner.get_mut(key).filter(|node| !node.is_deleted())
+    }
+    pub fn remove(&mut self, key: Key) -> Option<Value> {
+        let node = self.inner.remove(&key)?;
+        let (connections, value) = node.destruct();
+        for ref connected in connections {
+            // soft removed nodes can still point at nodes that were removed after them
+            if let Some(connected) = self.inner.get_mut(connected) {
+                connected.remove_connection(&key);
+            }
+        }
+        Some(value)
+    }
+    /// hides the node from `get`, `iter`, `select` and friends but keeps its value and
+    /// connections around so `restore` can bring it back. `purge_deleted` removes it for real.
+    /// the node keeps its key while it's soft removed
+    pub fn soft_remove(&mut self, key: &Key) -> bool {
+        let Some(node) = self.live(key) else {
+            return false;
+        };
+        let neighbors: HashSet<Key> = node
+            .connections
+            .values()
+            .chain(node.incoming.values())
+            .flatten()
+            .copied()
+            .collect();
+        let mut tombstone = Tombstone::default();
+        for neighbor in &neighbors {
+            if let Some(neighbor_node) = self.inner.get_mut(neighbor) {
+                let (connections, incoming) = neighbor_node.take_connections_to(key);
+                tombstone.add(*neighbor, connections, incoming);
+            }
+        }
+        self.inner.get_mut(key).unwrap().tombstone = Some(tombstone);
+        true
+    }
+    /// moves the node at `old` to `new` and points every connection to it at `new`.
+    /// soft removed nodes still hold on to their keys so `new` can't be one of them
+    pub fn remap_key(&mut self, old: Key, new: Key) -> Result<(), RemapError> {
+        let Some(node) = self.live(&old) else {
+            return Err(RemapError::Missing(old));
+        };
+        if old == new {
+            return Ok(());
+        }
+        if self.inner.contains_key(&new) {
+            return Err(KeyExists(new).into());
+        }
+        let neighbors: HashSet<Key> = node
+            .connections
+            .values()
+            .chain(node.incoming.values())
+            .flatten()
+            .copied()
+            .collect();
+        for neighbor in &neighbors {
+            if let Some(neighbor) = self.inner.get_mut(neighbor) {
+                neighbor.rename_connections(&old, new);
+            }
+        }
+        // soft removed nodes took their connections out of `old` so they aren't neighbors,
+        // and nodes with weak connections to `old` aren't either
+        for node in self.inner.values_mut() {
+            if node.is_deleted() {
+                node.rename_connections(&old, new);
+            }
+            node.rename_weak(&old, new);
+        }
+        let node = self.inner.remove(&old).unwrap();
+        self.keys.reserve(new);
+        self.inner.insert(new, node);
+        Ok(())
+    }
+    /// groups of nodes whose values are byte for byte the same and of the same type, each
+    /// sorted so the oldest node comes first. values of different types can have the same
+    /// bytes, like `1u32` and `[1u8, 0, 0, 0]`, and values from `Value::from_bytes` have no
+    /// type so they only match each other
+    pub fn duplicate_values(&self) -> Vec<Vec<Key>> {
+        let mut groups: HashMap<_, Vec<Key>> = HashMap::new();
+        for (key, node) in self.iter() {
+            let value = &node.value;
+            groups
+                .entry((value.tag, value.compressed, &value.bytes))
+                .or_default()
+                .push(*key);
+        }
+        groups
+            .into_values()
+            .filter(|keys| keys.len() > 1)
+            .map(|mut keys| {
+                keys.sort_unstable();
+                keys
+            })
+            .collect()
+    }
+    /// merges every group from `duplicate_values` into its oldest node, which takes over the
+    /// connections of the others before they're removed. connections between two nodes of
+    /// the same group are dropped since a node can't connect to itself. returns how many
+    /// nodes were removed
+    pub fn dedup_values(&mut self) -> usize {
+        let mut removed = 0;
+        for keys in self.duplicate_values() {
+            let (canonical, duplicates) = keys.split_first().unwrap();
+            for duplicate in duplicates {
+                let node = self.inner.remove(duplicate).unwrap();
+                let neighbors: HashSet<Key> = node
+                    .connections
+                    .values()
+                    .chain(node.incoming.values())
+                    .flatten()
+                    .copied()
+                    .collect();
+                for neighbor in &neighbors {
+                    if let Some(neighbor) = self.inner.get_mut(neighbor) {
+                        neighbor.rename_connections(duplicate, *canonical);
+                    }
+                }
+                // same as `remap_key`, soft removed nodes keep their connections in tombstones
+                for node in self.inner.values_mut() {
+                    if node.is_deleted() {
+                        node.rename_connections(duplicate, *canonical);
+                    }
+                    node.rename_weak(duplicate, *canonical);
+                }
+                let canonical_node = self.inner.get_mut(canonical).unwrap();
+                canonical_node.remove_connection(canonical);
+                canonical_node.absorb(canonical, node);
+                removed += 1;
+            }
+        }
+        removed
+    }
+    /// new node with the same value as `key`, `None` if it's missing or soft removed. with
+    /// `copy_edges` it also gets every connection and weak connection `key` has, and each
+    /// neighbor connects back to it under the same kinds it connects to `key` with. panics if
+    /// there are no keys left or the database is full, same as `create`
+    pub fn duplicate(&mut self, key: &Key, copy_edges: bool) -> Option<Key> {
+        let original = self.live(key)?;
+        let now = self.edge_timestamps.then(Utc::now);
+        let mut copy = Node::from_value(original.value.clone());
+        if copy_edges {
+            copy.connections = original.connections.clone();
+            copy.incoming = original.incoming.clone();
+            copy.weak = original.weak.clone();
+            for (kind, target) in original.edges() {
+                copy.stamp(kind, *target, now);
+            }
+        }
+        let neighbors = copy.neighbors();
+        let new = self.insert_generated(copy).unwrap();
+        for neighbor in neighbors {
+            let neighbor = self.inner.get_mut(&neighbor).unwrap();
+            let back = |kinds: &HashMap<String, KeySet>| -> Vec<String> {
+                kinds
+                    .iter()
+                    .filter(|(_kind, nodes)| nodes.contains(key))
+                    .map(|(kind, _nodes)| kind.clone())
+                    .collect()
+            };
+            let (connections, incoming) = (back(&neighbor.connections), back(&neighbor.incoming));
+            for kind in connections {
+                neighbor.stamp(&kind, new, now);
+                neighbor.connect(kind, new);
+            }
+            for kind in incoming {
+                neighbor.connect_incoming(kind, new);
+            }
+        }
+        Some(new)
+    }
+    /// puts `node` at `key` and returns the node that was there, soft removed or not. the old
+    /// node's connections are cleaned up the same as `remove` does. connections of `node` to
+    /// itself or to nodes that are missing or soft removed are dropped, every other node it
+    /// connects to gets the same kind back, as incoming for directed kinds. that means pairs of
+    /// kinds like `parent` and `child` come back as `parent` both ways, `connect` them again
+    /// afterwards. panics if the value doesn't pass the checks, see `try_replace_node`
+    pub fn replace_node(&mut self, key: Key, node: Node) -> Option<Node> {
+        self.try_replace_node(key, node).unwrap()
+    }
+    pub fn try_replace_node(
+        &mut self,
+        key: Key,
+        mut node: Node,
+    ) -> Result<Option<Node>, CreateError> {
+        if !self.inner.contains_key(&key) {
+            self.room(1)?;
+        }
+        self.checks.check(&mut node.value)?;
+        let old = self.inner.remove(&key);
+        for neighbor in old.iter().flat_map(Node::neighbors) {
+            // soft removed nodes can still point at nodes that were removed after them
+            if let Some(neighbor) = self.inner.get_mut(&neighbor) {
+                neighbor.remove_connection(&key);
+            }
+        }
+        let dangling: HashSet<Key> = node
+            .neighbors()
+            .into_iter()
+            .filter(|neighbor| self.live(neighbor).is_none())
+            .collect();
+        node.remove_connections(&dangling);
+        node.tombstone = None;
+        let edges: Vec<(Key, String, bool)> = node
+            .connections
+            .iter()
+            .map(|(kind, targets)| (kind, targets, false))
+            .chain(
+                node.incoming
+                    .iter()
+                    .map(|(kind, sources)| (kind, sources, true)),
+            )
+            .flat_map(|(kind, nodes, incoming)| {
+                nodes
+                    .iter()
+                    .map(move |neighbor| (*neighbor, kind.clone(), incoming))
+            })
+            .collect();
+        for (neighbor, kind, incoming) in edges {
+            let directed = self.kind(&kind) == Some(Directionality::Directed);
+            let neighbor = self.inner.get_mut(&neighbor).unwrap();
+            if directed && !incoming {
+                neighbor.connect_incoming(kind, key);
+            } else {
+                neighbor.connect(kind, key);
+            }
+        }
+        self.keys.reserve(key);
+        self.inner.insert(key, node);
+        Ok(old)
+    }
+    /// undoes `soft_remove`, connections to nodes that were removed in the meantime are dropped
+    pub fn restore(&mut self, key: &Key) -> bool {
+        let Some(tombstone) = self
+            .inner
+            .get_mut(key)
+            .and_then(|node| node.tombstone.take())
+        else {
+            return false;
+        };
+        for (neighbor, kind) in tombstone.connections {
+            if let Some(neighbor) = self.inner.get_mut(&neighbor) {
+                neighbor.connect(kind, *key);
+            }
+        }
+        for (neighbor, kind) in tombstone.incoming {
+            if let Some(neighbor) = self.inner.get_mut(&neighbor) {
+                neighbor.connect_incoming(kind, *key);
+            }
+        }
+        // whatever this node points at that is gone or soft removed itself now has to be
+        // handed over to that node's tombstone, same as if it was removed after this one
+        let node = &self.inner[key];
+        let stale: HashSet<Key> = node
+            .connections
+            .values()
+            .chain(node.incoming.values())
+            .flatten()
+            .filter(|neighbor| self.live(neighbor).is_none())
+            .copied()
+            .collect();
+        let node = self.inner.get_mut(key).unwrap();
+        let taken: Vec<_> = stale
+            .into_iter()
+            .map(|neighbor| (neighbor, node.take_connections_to(&neighbor)))
+            .collect();
+        for (neighbor, (connections, incoming)) in taken {
+            if let Some(tombstone) = self
+                .inner
+                .get_mut(&neighbor)
+                .and_then(|neighbor| neighbor.tombstone.as_mut())
+            {
+                tombstone.add(*key, connections, incoming);
+            }
+        }
+        true
+    }
+    /// removes every soft removed node for real, returns how many there were
+    pub fn purge_deleted(&mut self) -> usize {
+        let deleted: Vec<Key> = self
+            .inner
+            .iter()
+            .filter_map(|(key, node)| node.is_deleted().then_some(*key))
+            .collect();
+        self.remove_many(deleted).len()
+    }
+    /// removes all of `keys` and cleans up each neighbor once no matter how many of the removed
+    /// nodes it was connected to, values are returned in the same order as `keys`
+    pub fn remove_many(&mut self, keys: impl IntoIterator<Item = Key>) -> Vec<Option<Value>> {
+        let mut removed: HashSet<Key> = HashSet::new();
+        let mut neighbors: HashSet<Key> = HashSet::new();
+        let values = keys
+            .into_iter()
+            .map(|key| {
+                let node = self.inner.remove(&key)?;
+                removed.insert(key);
+                let (connections, value) = node.destruct();
+                neighbors.extend(connections);
+                Some(value)
+            })
+            .collect();
+        for neighbor in neighbors.difference(&removed) {
+            if let Some(neighbor) = self.inner.get_mut(neighbor) {
+                neighbor.remove_connections(&removed);
+            }
+        }
+        values
+    }
+    /// removes `key` and everything reachable from it through `kind` connections,
+    /// like deleting a folder deletes its children. returns the removed keys
+    pub fn remove_cascade(&mut self, key: Key, kind: &str) -> Vec<Key> {
+        if self.live(&key).is_none() {
+            return Vec::new();
+        }
+        let mut visited: HashSet<Key> = HashSet::from([key]);
+        let mut queue: VecDeque<Key> = VecDeque::from([key]);
+        let mut removed: Vec<Key> = Vec::new();
+        while let Some(current) = queue.pop_front() {
+            removed.push(current);
+            for next in self.select(&current, kind) {
+                if visited.insert(*next) {
+                    queue.push_back(*next);
+                }
+            }
+        }
+        self.remove_many(removed.iter().copied());
+        removed
+    }
+    /// nodes exactly `distance` `kind` connections away from `start` by the shortest path,
+    /// so a distance of 2 is friends of friends that aren't friends already
+    pub fn nodes_at_distance(&self, start: &Key, kind: &str, distance: usize) -> HashSet<Key> {
+        if self.live(start).is_none() {
+            return HashSet::new();
+        }
+        let mut visited: HashSet<Key> = HashSet::from([*start]);
+        let mut ring: HashSet<Key> = HashSet::from([*start]);
+        for _ in 0..distance {
+            ring = ring
+                .iter()
+                .flat_map(|key| self.select(key, kind))
+                .filter(|next| visited.insert(**next))
+                .copied()
+                .collect();
+            if ring.is_empty() {
+                break;
+            }
+        }
+        ring
+    }
+    /// kinds that aren't declared connect each side under whatever kind is passed for it.
+    /// declaring a kind doesn't change connections that already exist, but a kind that
+    /// becomes `Directed` gets its incoming side built from them like `rebuild_indexes` does
+    pub fn declare_kind(&mut self, kind: impl Into<String>, directionality: Directionality) {
+        let kind = kind.into();
+        let previous = self.kinds.insert(kind.clone(), directionality);
+        if directionality == Directionality::Directed && previous != Some(directionality) {
+            self.rebuild_incoming(&[kind]);
+        }
+    }
+    pub fn kind(&self, kind: &str) -> Option<Directionality> {
+        self.kinds.get(kind).copied()
+    }
+    /// recomputes the incoming side of every kind declared `Directed` from the forward
+    /// connections, running it twice changes nothing. kinds that aren't declared keep the
+    /// incoming connections that were saved with them. soft removed nodes keep theirs as they
+    /// are for `restore`, and aren't counted as sources either
+    pub fn rebuild_indexes(&mut self) {
+        let directed: Vec<String> = self
+            .kinds
+            .iter()
+            .filter(|(_kind, directionality)| **directionality == Directionality::Directed)
+            .map(|(kind, _directionality)| kind.clone())
+            .collect();
+        self.rebuild_incoming(&directed);
+    }
+    /// `rebuild_indexes` for only the kinds in `directed`
+    fn rebuild_incoming(&mut self, directed: &[String]) {
+        if directed.is_empty() {
+            return;
+        }
+        let edges: Vec<(Key, &String, Key)> = self
+            .inner
+            .iter()
+            .filter(|(_from, node)| !node.is_deleted())
+            .flat_map(|(from, node)| {
+                directed.iter().flat_map(move |kind| {
+                    node.get_connections(kind)
+                        .iter()
+                        .map(move |to| (*from, kind, *to))
+                })
+            })
+            .collect();
+        for node in self.inner.values_mut().filter(|node| !node.is_deleted()) {
+            for kind in directed {
+                if let Some(incoming) = node.incoming.get_mut(kind) {
+                    incoming.clear();
+                }
+            }
+        }
+        for (from, kind, to) in edges {
+            if let Some(node) = self.inner.get_mut(&to).filter(|node| !node.is_deleted()) {
+                node.connect_incoming(kind.clone(), from);
+            }
+        }
+    }
+    /// false if either node is missing or deleted, if the kinds go against how they're
+    /// declared, if both keys are the same since a node can't connect to itself, or if the
+    /// nodes are already connected under other kinds with `set_unique_targets` on
+    pub fn connect(
+        &mut self,
+        first_key: Key,
+        first_kind: impl Into<String>,
+        second_key: Key,
+        second_kind: impl Into<String>,
+    ) -> bool {
+        if first_key == second_key {
+            return false;
+        }
+        let (first_kind, second_kind) = (first_kind.into(), second_kind.into());
+        let first = self.kind(&first_kind);
+        let second = self.kind(&second_kind);
+        let undirected = [first, second].contains(&Some(Directionality::Undirected));
+        if undirected && first_kind != second_kind {
+            return false;
+        }
+        let one_way = first == Some(Directionality::Directed) && first_kind == second_kind;
+        let directed = |kind| kind == Some(Directionality::Directed);
+        let (first_directed, second_directed) = (directed(first), directed(second));
+        let [Some(node1), Some(node2)] = self.inner.get_disjoint_mut([&first_key, &second_key])
+        else {
+            return false;
+        };
+        if node1.is_deleted() || node2.is_deleted() {
+            return false;
+        }
+        if self.unique_targets
+            && (node1.connects_otherwise(&second_key, &first_kind)
+                || node2.connects_otherwise(&first_key, &second_kind))
+        {
+            return false;
+        }
+        let now = self.edge_timestamps.then(Utc::now);
+        node1.stamp(&first_kind, second_key, now);
+        // a directed kind always leaves the target knowing it's connected to, whatever kind
+        // goes back, the same as `rebuild_indexes` would
+        if first_directed {
+            node2.connect_incoming(first_kind.clone(), first_key);
+        }
+        node1.connect(first_kind, second_key);
+        if !one_way {
+            if second_directed {
+                node1.connect_incoming(second_kind.clone(), second_key);
+            }
+            node2.stamp(&second_kind, first_key, now);
+            node2.connect(second_kind, first_key);
+        }
+        true
+    }
+    /// same as `connect` but false if `first_key` is already connected to `second_key` under
+    /// `first_kind`, so true means the connection is new. an existing connection keeps its
+    /// time from `set_edge_timestamps`
+    pub fn connect_if_absent(
+        &mut self,
+        first_key: Key,
+        first_kind: impl Into<String>,
+        second_key: Key,
+        second_kind: impl Into<String>,
+    ) -> bool {
+        let first_kind = first_kind.into();
+        if self.select(&first_key, &first_kind).contains(&second_key) {
+            return false;
+        }
+        self.connect(first_key, first_kind, second_key, second_kind)
+    }
+    /// same as `connect` but returns the total degree of both nodes after connecting them,
+    /// `None` if either key is missing
+    pub fn connect_counted(
+        &mut self,
+        first_key: Key,
+        first_kind: impl Into<String>,
+        second_key: Key,
+        second_kind: impl Into<String>,
+    ) -> Option<(usize, usize)> {
+        if !self.connect(first_key, first_kind, second_key, second_kind) {
+            return None;
+        }
+        Some((
+            self.inner[&first_key].total_degree(),
+            self.inner[&second_key].total_degree(),
+        ))
+    }
+    /// connects `center` to every one of `leaves` under `kind` both ways, or with the leaves
+    /// getting it as incoming when `kind` is declared directed. leaves that are missing, soft
+    /// removed, `center` itself or already connected from `center` are skipped, so it returns
+    /// how many connections are new. with `set_unique_targets` on so are leaves connected to
+    /// or from `center` under other kinds. nothing happens if `center` is missing or soft removed
+    pub fn connect_star(
+        &mut self,
+        center: Key,
+        kind: impl Into<String>,
+        leaves: impl IntoIterator<Item = Key>,
+    ) -> usize {
+        let kind = kind.into();
+        let Some(center_node) = self.live(&center) else {
+            return 0;
+        };
+        let existing = center_node.get_connections(&kind).clone();
+        let unique = self.unique_targets;
+        // leaves `center` already connects to under other kinds
+        let taken: HashSet<Key> = if unique {
+            center_node
+                .connections
+                .iter()
+                .filter(|(other, _nodes)| **other != kind)
+                .flat_map(|(_other, nodes)| nodes.iter().copied())
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        let one_way = self.kind(&kind) == Some(Directionality::Directed);
+        let now = self.edge_timestamps.then(Utc::now);
+        let mut added: HashSet<Key> = HashSet::new();
+        for leaf in leaves {
+            if leaf == center || existing.contains(&leaf) || added.contains(&leaf) {
+                continue;
+            }
+            if taken.contains(&leaf) {
+                continue;
+            }
+            let Some(node) = self.live_mut(&leaf) else {
+                continue;
+            };
+            if unique && node.connects_otherwise(&center, &kind) {
+                continue;
+            }
+            if one_way {
+                node.connect_incoming(kind.clone(), center);
+            } else {
+                node.stamp(&kind, center, now);
+                node.connect(kind.clone(), center);
+            }
+            added.insert(leaf);
+        }
+        let center_node = self.inner.get_mut(&center).unwrap();
+        for leaf in &added {
+            center_node.stamp(&kind, *leaf, now);
+            center_node.connect(kind.clone(), *leaf);
+        }
+        added.len()
+    }
+    /// records that the node was used just now without reading or changing it, for caches
+    /// that evict the least recently used nodes. see `Node::touched_at`
+    pub fn touch(&mut self, key: &Key) -> bool {
+        let Some(node) = self.live_mut(key) else {
+            return false;
+        };
+        node.touched_at = Some(Utc::now().timestamp_micros());
+        true
+    }
+    /// one way connection from `from` to `to` that's only stored on `from`, so removing `to`
+    /// doesn't have to touch `from`. removing a node with 10k connections took 630µs, with
+    /// 10k weak ones pointing at it under 1µs (`cargo bench -- hub`), in exchange
+    /// `select_weak` checks every target is still there. false if either node is missing or
+    /// soft removed or both keys are the same
+    pub fn connect_weak(&mut self, from: Key, kind: impl Into<String>, to: Key) -> bool {
+        if from == to || self.live(&to).is_none() {
+            return false;
+        }
+        let Some(node) = self.live_mut(&from) else {
+            return false;
+        };
+        node.weak.entry(kind.into()).or_default().insert(to);
+        true
+    }
+    /// removes a weak connection, false if there wasn't one
+    pub fn disconnect_weak(&mut self, from: &Key, kind: &str, to: &Key) -> bool {
+        self.live_mut(from)
+            .and_then(|node| node.weak.get_mut(kind))
+            .is_some_and(|nodes| nodes.remove(to))
+    }
+    /// targets of the weak connections of `key` under `kind` that still exist, targets that
+    /// are soft removed are skipped too and come back with `restore`
+    pub fn select_weak(&self, key: &Key, kind: &str) -> impl Iterator<Item = Key> {
+        self.live(key)
+            .map_or(&EMPTY_KEYSET, |node| node.get_weak(kind))
+            .iter()
+            .filter(|target| self.live(target).is_some())
+            .copied()
+    }
+    /// drops weak connections to nodes that were removed, returns how many. soft removed
+    /// targets are kept since they might be restored. a key that's reused by
+    /// `create_with_key` after being removed would otherwise pick up the stale connections
+    pub fn prune_weak(&mut self) -> usize {
+        let gone: HashSet<Key> = self
+            .inner
+            .values()
+            .flat_map(|node| node.weak.values().flatten())
+            .filter(|target| !self.inner.contains_key(target))
+            .copied()
+            .collect();
+        let mut pruned = 0;
+        for node in self.inner.values_mut() {
+            for nodes in node.weak.values_mut() {
+                let before = nodes.len();
+                nodes.retain(|target| !gone.contains(target));
+                pruned += before - nodes.len();
+            }
+        }
+        pruned
+    }
+    /// makes `connect` record when each connection is made, for `edge_created_at`. the times
+    /// are saved with the database. connections made while it's off have no time, making
+    /// a connection again replaces its time
+    pub fn set_edge_timestamps(&mut self, enabled: bool) {
+        self.edge_timestamps = enabled;
+    }
+    /// makes `connect` refuse to connect two nodes that are already connected under a
+    /// different kind either way, so every pair of nodes has at most one kind between them.
+    /// connections that are already there when it's turned on are left alone
+    pub fn set_unique_targets(&mut self, enabled: bool) {
+        self.unique_targets = enabled;
+    }
+    /// when `first_key` was connected to `second_key` under `kind`, `None` if they aren't
+    /// connected or the connection was made without `set_edge_timestamps`
+    pub fn edge_created_at(
+        &self,
+        first_key: &Key,
+        kind: &str,
+        second_key: &Key,
+    ) -> Option<DateTime<Utc>> {
+        self.live(first_key)?.connected_at(kind, second_key)
+    }
+    /// removes every connection between the two nodes in both directions, whatever the kind.
+    /// false if either node is missing or deleted, or if both keys are the same
+    pub fn disconnect(&mut self, first_key: &Key, second_key: &Key) -> bool {
+        if first_key == second_key {
+            return false;
+        }
+        let [Some(node1), Some(node2)] = self.inner.get_disjoint_mut([first_key, second_key])
+        else {
+            return false;
+        };
+        if node1.is_deleted() || node2.is_deleted() {
+            return false;
+        }
+        node1.remove_connection(second_key);
+        node2.remove_connection(first_key);
+        true
+    }
+    /// removes every connection `key` has under `kind` and returns how many there were. a
+    /// neighbor `key` isn't connected to in any other way loses every connection back to it,
+    /// so the other side of a pair of kinds like `parent` and `child` goes too. when they're
+    /// still connected under another kind there's no telling which of the neighbor's kinds
+    /// went with `kind`, so it only loses `kind`, as incoming for directed kinds
+    pub fn disconnect_kind_all(&mut self, key: &Key, kind: &str) -> usize {
+        let Some(node) = self.live_mut(key) else {
+            return 0;
+        };
+        let Some(neighbors) = node.connections.remove(kind) else {
+            return 0;
+        };
+        node.connected_at.remove(kind);
+        let still_connected: HashSet<Key> = neighbors
+            .iter()
+            .filter(|neighbor| {
+                node.connections
+                    .values()
+                    .chain(node.incoming.values())
+                    .any(|nodes| nodes.contains(neighbor))
+            })
+            .copied()
+            .collect();
+        let directed = self.kind(kind) == Some(Directionality::Directed);
+        for neighbor_key in &neighbors {
+            let Some(neighbor) = self.inner.get_mut(neighbor_key) else {
+                continue;
+            };
+            if !still_connected.contains(neighbor_key) {
+                neighbor.remove_connection(key);
+                continue;
+            }
+            let back = if directed {
+                &mut neighbor.incoming
+            } else {
+                &mut neighbor.connections
+            };
+            if let Some(nodes) = back.get_mut(kind) {
+                nodes.remove(key);
+            }
+            if let Some(times) = neighbor.connected_at.get_mut(kind) {
+                times.remove(key);
+            }
+        }
+        neighbors.len()
+    }
+    pub fn select(&self, key: &Key, kind: &str) -> &KeySet {
+        let Some(node) = self.live(key) else {
+            return &EMPTY_KEYSET;
+        };
+        node.get_connections(kind)
+    }
+    /// nodes `key` connects to under `kind` whose value reads as the archived type `T` and
+    /// passes `pred`, sorted by key. values that aren't a `T` don't match
+    pub fn neighbors_matching<T>(
+        &self,
+        key: &Key,
+        kind: &str,
+        pred: impl Fn(&T) -> bool,
+    ) -> Vec<Key>
+    where
+        T: Portable + for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    {
+        let mut matching: Vec<Key> = self
+            .select(key, kind)
+            .iter()
+            .filter(|neighbor| {
+                self.get_value(neighbor)
+                    .and_then(Value::deserialize::<T>)
+                    .is_some_and(&pred)
+            })
+            .copied()
+            .collect();
+        matching.sort_unstable();
+        matching
+    }
+    /// how many connections `key` has under `kind`, the per kind `Node::total_degree`
+    pub fn connection_count(&self, key: &Key, kind: &str) -> usize {
+        self.select(key, kind).len()
+    }
+    /// owned `Node::connections`, empty if the node is missing
+    pub fn kind_counts(&self, key: &Key) -> HashMap<String, usize> {
+        let Some(node) = self.live(key) else {
+            return HashMap::new();
+        };
+        node.connections()
+            .map(|(kind, count)| (kind.to_owned(), count))
+            .collect()
+    }
+    /// whether `a` connects to `b` under any kind, a connection only `b` has to `a` under a
+    /// directed kind doesn't count
+    pub fn are_adjacent(&self, a: &Key, b: &Key) -> bool {
+        let Some(node) = self.live(a) else {
+            return false;
+        };
+        self.live(b).is_some() && node.connections.values().any(|nodes| nodes.contains(b))
+    }
+    /// everything connected to `key` regardless of the kind of connection
+    pub fn select_all_kinds(&self, key: &Key) -> HashSet<Key> {
+        let Some(node) = self.live(key) else {
+            return HashSet::new();
+        };
+        node.connections.values().flatten().copied().collect()
+    }
+    pub fn get(&self, key: &Key) -> Option<&Node> {
+        self.live(key)
+    }
+    /// `get` for when only the value is needed
+    pub fn get_value(&self, key: &Key) -> Option<&Value> {
+        self.get(key).map(Node::value)
+    }
+    /// the values of both nodes at once for editing them together, `None` if the keys are the
+    /// same or either node is missing or soft removed. only the values so the connections
+    /// stay consistent, and like `for_each_value_mut` the results aren't checked
+    pub fn get_pair_mut(&mut self, a: &Key, b: &Key) -> Option<(&mut Value, &mut Value)> {
+        // `get_disjoint_mut` panics on overlapping keys
+        if a == b {
+            return None;
+        }
+        let [Some(first), Some(second)] = self.inner.get_disjoint_mut([a, b]) else {
+            return None;
+        };
+        if first.is_deleted() || second.is_deleted() {
+            return None;
+        }
+        Some((&mut first.value, &mut second.value))
+    }
+    /// gives `a` the value of `b` and the other way around, keys and connections stay where
+    /// they are. false if either node is missing or soft removed
+    pub fn swap_values(&mut self, a: &Key, b: &Key) -> bool {
+        if a == b {
+            return self.get(a).is_some();
+        }
+        let Some((first, second)) = self.get_pair_mut(a, b) else {
+            return false;
+        };
+        std::mem::swap(first, second);
+        true
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Node)> {
+        self.inner.iter().filter(|(_key, node)| !node.is_deleted())
+    }
+    /// nothing stops `Node::connect` or `Node::remove_connection` from leaving a connection
+    /// that the other node doesn't have back, which `remove` and friends then can't clean up.
+    /// `for_each_value_mut` only hands out the values
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Key, &mut Node)> {
+        self.inner
+            .iter_mut()
+            .filter(|(_key, node)| !node.is_deleted())
+    }
+    /// edits every value in one pass, like `set_value_bytes` the results aren't checked
+    pub fn for_each_value_mut(&mut self, mut f: impl FnMut(&Key, &mut Value)) {
+        for (key, node) in self.iter_mut() {
+            f(key, &mut node.value);
+        }
+    }
+    /// nodes whose `Node::total_degree` is at least `min` and at most `max`
+    pub fn nodes_with_degree(&self, min: usize, max: usize) -> impl Iterator<Item = (&Key, &Node)> {
+        self.iter()
+            .filter(move |(_key, node)| (min..=max).contains(&node.total_degree()))
+    }
+    /// generated keys are timestamps so this is the oldest node
+    pub fn min_key(&self) -> Option<Key> {
+        self.iter().map(|(key, _node)| *key).min()
+    }
+    /// the newest node, see `min_key`
+    pub fn max_key(&self) -> Option<Key> {
+        self.iter().map(|(key, _node)| *key).max()
+    }
+    /// sorted keys from `start` up to but not including `end`, since generated keys are
+    /// timestamps this is everything created in that window
+    pub fn keys_in_range(&self, start: Key, end: Key) -> Vec<Key> {
+        let mut keys: Vec<Key> = self
+            .iter()
+            .map(|(key, _node)| *key)
+            .filter(|key| (start..end).contains(key))
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+    /// every `(source, kind, target)` connection. with `dedupe` a connection that has the same
+    /// kind going back the other way is only yielded once, from the smaller key
+    pub fn edges(&self, dedupe: bool) -> impl Iterator<Item = (Key, &str, Key)> {
+        self.iter()
+            .flat_map(|(source, node)| {
+                node.edges()
+                    .map(move |(kind, target)| (*source, kind, *target))
+            })
+            .filter(move |(source, kind, target)| {
+                !dedupe || source <= target || !self.select(target, kind).contains(source)
+            })
+    }
+    /// connections made at or after `since` while `set_edge_timestamps` was on, looks at
+    /// every connection so it's O(edges)
+    pub fn edges_since(&self, since: DateTime<Utc>) -> impl Iterator<Item = (Key, &str, Key)> {
+        self.iter().flat_map(move |(source, node)| {
+            node.edges()
+                .filter(move |(kind, target)| {
+                    node.connected_at(kind, target)
+                        .is_some_and(|at| at >= since)
+                })
+                .map(move |(kind, target)| (*source, kind, *target))
+        })
+    }
+    pub fn query(&self) -> Query<'_, S> {
+        Query::new(self)
+    }
+    /// every value read as the archived type `T`, `None` for values that aren't a `T`
+    pub fn values_as<'db, T>(&'db self) -> impl Iterator<Item = (Key, Option<&'db T>)>
+    where
+        T: Portable + 'db + for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    {
+        self.iter()
+            .map(|(key, node)| (*key, node.value.deserialize::<T>()))
+    }
+    /// keys of every node whose value reads as the archived type `T`, sorted by `key_fn` of it
+    /// and then by key. nodes that aren't a `T` are left out
+    pub fn order_by_value<T, K: Ord>(&self, key_fn: impl Fn(&T) -> K) -> Vec<Key>
+    where
+        T: Portable + for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    {
+        let mut sorted: Vec<(K, Key)> = self
+            .values_as::<T>()
+            .filter_map(|(key, value)| Some((key_fn(value?), key)))
+            .collect();
+        sorted.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+        sorted.into_iter().map(|(_sort, key)| key).collect()
+    }
+    /// replaces every value that deserializes as `T` with `f` of it, for migrating stored data
+    /// to a new type. returns the keys of the nodes that were skipped because they aren't a `T`,
+    /// soft removed nodes are left as they are
+    pub fn map_values<T, U, F>(&mut self, f: F) -> Vec<Key>
+    where
+        T: rkyv::Archive,
+        T::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>
+            + rkyv::Deserialize<T, HighDeserializer<rancor::Error>>,
+        U: for<'a> rkyv::Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+        F: Fn(T) -> U,
+    {
+        let mut serializer = Serializer::new();
+        let mut skipped: Vec<Key> = Vec::new();
+        for (key, node) in self
+            .inner
+            .iter_mut()
+            .filter(|(_key, node)| !node.is_deleted())
+        {
+            match node.value.deserialize_owned::<T>() {
+                Some(value) => node.value = serializer.serialize(&f(value)),
+                None => skipped.push(*key),
+            }
+        }
+        skipped
+    }
+    /// releases memory left over from removed nodes and connections
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.values_mut().for_each(Node::shrink_to_fit);
+        self.inner.shrink_to_fit();
+    }
+
+    /// panics if the database can't be written, see `try_save`
+    pub fn save(&self) {
+        self.try_save().unwrap();
+    }
+    pub fn try_save(&self) -> Result<(), SaveError> {
+        let start = Instant::now();
+        if let Some(bytes) =
+            self.storage
+                .save(&self.inner, self.blob_threshold, self.durable, self.backups)?
+        {
+            self.inner.clean();
+            self.last_save.set(SaveStats {
+                bytes,
+                duration: start.elapsed(),
+            });
+        }
+        Ok(())
+    }
+    /// writes the same bytes `save` puts in the database file to any sink,
+    /// values are always written inline since there's no directory to put blobs in
+    pub fn save_to_writer<W: Write>(&self, w: &mut W) -> Result<(), SaveError> {
+        let start = Instant::now();
+        let stored = blob::externalize(&self.inner, None, false)?;
+        let bytes = snapshot::write(&stored, w)?;
+        self.last_save.set(SaveStats {
+            bytes,
+            duration: start.elapsed(),
+        });
+        Ok(())
+    }
+    /// the most recent successful `save` or `save_to_writer`, `None` if nothing has been saved
+    /// yet. `save` on an in-memory database writes nothing so it isn't recorded
+    pub fn last_save_stats(&self) -> Option<SaveStats> {
+        self.last_save.get()
+    }
+    /// how the database was loaded, `None` if it was created with `in_memory`
+    pub fn last_load_stats(&self) -> Option<LoadStats> {
+        self.last_load
+    }
+    /// values with more than `threshold` bytes get saved to their own files in a sidecar
+    /// directory next to the database file instead of inside the database file,
+    /// keeps big values from being rewritten on every save. `None` stores everything inline
+    pub fn set_blob_threshold(&mut self, threshold: Option<usize>) {
+        self.blob_threshold = threshold;
+    }
+    /// values that serialize to more than `threshold` bytes get zstd compressed at `level`
+    /// when they're stored, and decompressed the first time they're read. the decompressed
+    /// bytes are kept next to the compressed ones from then on, so this saves the most memory
+    /// for values that are rarely read. values already in the database stay how they are
+    /// until they're written again, and `set_value_bytes` and `map_values` never compress.
+    /// `None` stops compressing new values. creating 10k small string nodes took
+    /// 5.0ms uncompressed, 5.5ms with a threshold they were all under and 62ms with every
+    /// value compressed, so keep the threshold well above the small values.
+    /// `cargo bench --features zstd -- value_compression` to measure it again
+    #[cfg(feature = "zstd")]
+    pub fn set_value_compression(&mut self, threshold: Option<usize>, level: i32) {
+        self.checks.compression = threshold.map(|threshold| (threshold, level));
+    }
+    /// makes `save` call `File::sync_all` on the database file, any new blobs and the
+    /// directories they're in, so once it returns the bytes are on the disk and not just in
+    /// the OS page cache where a power failure loses them. every save then waits for the disk
+    /// to flush, which costs a few milliseconds on an SSD and can be much more on spinning
+    /// disks or network filesystems, `cargo bench -- persistence` compares it to a plain save
+    pub fn set_durable(&mut self, durable: bool) {
+        self.durable = durable;
+    }
+    /// keeps the last `count` database files around as `db.grahh.1` for the one before the
+    /// latest save, `db.grahh.2` for the one before that and so on, rotated on every save.
+    /// rename a backup over `db.grahh` to go back to it. blobs are only kept for the latest
+    /// save, so values stored out of line with `set_blob_threshold` can be missing from older
+    /// backups. memory and custom storage have no files to keep
+    pub fn set_backups(&mut self, count: usize) {
+        self.backups = count;
+    }
+    /// saves the database when it's dropped, if the nodes changed since it was loaded or last
+    /// saved. anything that borrows the nodes mutably counts as a change, even if it ended up
+    /// changing nothing, and an explicit `save` counts as saving so dropping right after one
+    /// doesn't write again. clones start with it off so only the database it was set on writes,
+    /// and a `SharedDatabase` saves its latest generation when it's dropped instead. does
+    /// nothing for databases in memory
+    pub fn set_save_on_drop(&mut self, save_on_drop: SaveOnDrop) {
+        self.save_on_drop = OnDrop(save_on_drop);
+    }
+    /// picks keys for new nodes with `keys` from now on, the keys already in the database are
+    /// reserved with it first
+    pub fn set_key_generator(&mut self, keys: impl KeyGenerator + 'static) {
+        self.keys = Arc::new(keys);
+        self.reserve_keys();
+    }
+    /// keeps the key generator from handing out a key that's already in the database, in case
+    /// the clock went backwards since it was saved. tombstones count too so a key isn't reused
+    fn reserve_keys(&self) {
+        if let Some(max) = self.inner.keys().max() {
+            self.keys.reserve(*max);
+        }
+    }
+}
+
+impl<S> Database<S> {
+    /// what dropping does with `save_on_drop`, also used by `SharedDatabase` which holds on
+    /// to the setting itself
+    fn save_for_drop(&self, save_on_drop: SaveOnDrop) {
+        if save_on_drop == SaveOnDrop::Off || !self.inner.is_dirty() {
+            return;
+        }
+        let saved = self
+            .storage
+            .save(&self.inner, self.blob_threshold, self.durable, self.backups);
+        match saved {
+            Ok(_) => {}
+            Err(error) if save_on_drop == SaveOnDrop::Panic && !std::thread::panicking() => {
+                panic!("failed to save the database on drop: {error}")
+            }
+            Err(error) => eprintln!("failed to save the database on drop: {error}"),
+        }
+    }
+}
+
+impl<S> Drop for Database<S> {
+    fn drop(&mut self) {
+        self.save_for_drop(self.save_on_drop.0);
+    }
+}
+
+impl<S: BuildHasher + Default> Database<S> {
+    /// `load` with the node map hashed by `S`
+    pub fn load_with_hasher(path: PathBuf) -> Self {
+        Self::try_load_with_hasher(path).unwrap()
+    }
+    pub fn try_load_with_hasher(path: PathBuf) -> Result<Self, LoadError> {
+        Self::open(Storage::File(path), HashMap::new())
+    }
+    /// `in_memory` with the node map hashed by `S`
+    pub fn in_memory_with_hasher() -> Self {
+        Self {
+            inner: Tracked::new(HashMap::default()),
+            storage: Storage::Memory,
+            blob_threshold: None,
+            checks: Checks::default(),
+            keys: Arc::new(TimestampKeys::default()),
+            kinds: HashMap::new(),
+            max_nodes: None,
+            edge_timestamps: false,
+            unique_targets: false,
+            durable: false,
+            save_on_drop: OnDrop::default(),
+            backups: 0,
+            last_save: LastSave::default(),
+            last_load: None,
+        }
+    }
+    /// `kinds` are declared before the indexes are rebuilt
+    fn open(storage: Storage, kinds: HashMap<String, Directionality>) -> Result<Self, LoadError> {
+        let start = Instant::now();
+        let (inner, bytes) = storage.load()?;
+        Ok(Self::loaded(inner, storage, kinds, bytes, start))
+    }
+    fn from_reader(mut r: impl Read) -> Result<Self, LoadError> {
+        let start = Instant::now();
+        let (inner, bytes) = snapshot::read(&mut r)?;
+        if let Some(blob) = inner.values().find_map(|node| node.value.blob.clone()) {
+            return Err(LoadError::Blob(blob));
+        }
+        Ok(Self::loaded(
+            inner,
+            Storage::Memory,
+            HashMap::new(),
+            bytes,
+            start,
+        ))
+    }
+    /// the rest of `open` and `from_reader` once the nodes are read, `start` is when reading
+    /// them started
+    fn loaded(
+        inner: HashMap<Key, Node, S>,
+        storage: Storage,
+        kinds: HashMap<String, Directionality>,
+        bytes: usize,
+        start: Instant,
+    ) -> Self {
+        let mut db = Self::in_memory_with_hasher();
+        db.inner = Tracked::new(inner);
+        db.storage = storage;
+        db.kinds = kinds;
+        db.reserve_keys();
+        db.rebuild_indexes();
+        db.last_load = Some(LoadStats {
+            bytes,
+            duration: start.elapsed(),
+        });
+        // rebuilding the indexes borrowed the nodes mutably but they match what was loaded
+        db.inner.clean();
+        db
+    }
+}
+
+impl Database {
+    /// panics if the file can't be read or isn't a database, see `try_load`
+    pub fn load(path: PathBuf) -> Self {
+        Self::load_with_hasher(path)
+    }
+    pub fn try_load(path: PathBuf) -> Result<Self, LoadError> {
+        Self::try_load_with_hasher(path)
+    }
+    /// same as `load` but the database file is zstd compressed, see `Storage::CompressedFile`
+    #[cfg(feature = "zstd")]
+    pub fn load_compressed(path: PathBuf, level: i32) -> Self {
+        Self::try_load_compressed(path, level).unwrap()
+    }
+    #[cfg(feature = "zstd")]
+    pub fn try_load_compressed(path: PathBuf, level: i32) -> Result<Self, LoadError> {
+        Self::open(Storage::CompressedFile { path, level }, HashMap::new())
+    }
+    /// database kept in `backend`, `save` hands it the snapshot. `set_blob_threshold` and
+    /// `set_durable` do nothing here, durability is up to the backend
+    pub fn load_backend(backend: impl StorageBackend + 'static) -> Self {
+        Self::try_load_backend(backend).unwrap()
+    }
+    pub fn try_load_backend(backend: impl StorageBackend + 'static) -> Result<Self, LoadError> {
+        Self::open(Storage::Custom(Arc::new(backend)), HashMap::new())
+    }
+    /// checks that the database file at `path` would load, the snapshot is validated and every
+    /// blob it refers to has to exist, but nothing gets deserialized. it took 11ms where `load`
+    /// took 200ms for 100k nodes with 200k connections, `cargo bench -- persistence` to
+    /// measure it again. there's no checksum to check beyond the header, and zstd compressed
+    /// files aren't supported
+    pub fn verify(path: &Path) -> Result<(), LoadError> {
+        let mut bytes: AlignedVec = AlignedVec::new();
+        bytes.extend_from_reader(&mut File::open(path)?)?;
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        // files from the first release never stored values out of line
+        let Contents::Nodes(archive) = snapshot::access(&bytes)? else {
+            return Ok(());
+        };
+        let dir = blob::dir(path);
+        for node in archive.values() {
+            if let Some(blob) = node.value.blob.as_ref()
+                && !dir.join(blob.as_str()).is_file()
+            {
+                return Err(LoadError::Blob(blob.to_string()));
+            }
+        }
+        Ok(())
+    }
+    /// in-memory database from bytes written by `save_to_writer`
+    pub fn load_from_reader<R: Read>(r: R) -> Result<Self, LoadError> {
+        Self::from_reader(r)
+    }
+    pub fn in_memory() -> Self {
+        Self::in_memory_with_hasher()
+    }
+    /// for setting everything up front instead of calling setters after `load`
+    pub fn builder() -> DatabaseBuilder {
+        DatabaseBuilder::new()
+    }
+}
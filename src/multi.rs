@@ -0,0 +1,88 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{Database, LoadError, SaveError};
+
+/// several named databases saved side by side in one directory as `{namespace}.grahh`
+#[derive(Debug)]
+pub struct MultiDatabase {
+    dir: PathBuf,
+    databases: HashMap<String, Database>,
+}
+
+impl MultiDatabase {
+    /// loads every `.grahh` file already in `dir`, creating `dir` if it doesn't exist.
+    /// panics if any of it fails, see `try_open`
+    pub fn open(dir: PathBuf) -> Self {
+        Self::try_open(dir).unwrap()
+    }
+    pub fn try_open(dir: PathBuf) -> Result<Self, LoadError> {
+        fs::create_dir_all(&dir)?;
+        let mut databases = HashMap::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "grahh") {
+                continue;
+            }
+            let namespace = path.file_stem().unwrap().to_string_lossy().into_owned();
+            databases.insert(namespace, Database::try_load(path)?);
+        }
+        Ok(Self { dir, databases })
+    }
+    pub fn get(&self, namespace: &str) -> Option<&Database> {
+        self.databases.get(namespace)
+    }
+    pub fn get_mut(&mut self, namespace: &str) -> Option<&mut Database> {
+        self.databases.get_mut(namespace)
+    }
+    /// the database called `namespace`, loading or creating its file if it isn't open yet.
+    /// panics if that fails, see `try_get_or_create`
+    pub fn get_or_create(&mut self, namespace: &str) -> &mut Database {
+        self.try_get_or_create(namespace).unwrap()
+    }
+    pub fn try_get_or_create(&mut self, namespace: &str) -> Result<&mut Database, LoadError> {
+        let db = match self.databases.entry(namespace.to_owned()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(Database::try_load_namespaced(&self.dir, namespace)?)
+            }
+        };
+        Ok(db)
+    }
+    /// stops managing the database, its file is left where it is
+    pub fn remove(&mut self, namespace: &str) -> Option<Database> {
+        self.databases.remove(namespace)
+    }
+    pub fn namespaces(&self) -> impl Iterator<Item = &str> {
+        self.databases.keys().map(String::as_str)
+    }
+    /// panics if any database fails to save, see `try_save_all`
+    pub fn save_all(&self) {
+        self.try_save_all().unwrap();
+    }
+    /// saves every database even when one of them fails, and returns the first failure
+    pub fn try_save_all(&self) -> Result<(), SaveError> {
+        let mut saved = Ok(());
+        for db in self.databases.values() {
+            if let Err(error) = db.try_save()
+                && saved.is_ok()
+            {
+                saved = Err(error);
+            }
+        }
+        saved
+    }
+}
+
+impl Database {
+    /// `load` of `{namespace}.grahh` in `dir`, so databases sharing a directory don't collide
+    pub fn load_namespaced(dir: &Path, namespace: &str) -> Self {
+        Self::try_load_namespaced(dir, namespace).unwrap()
+    }
+    pub fn try_load_namespaced(dir: &Path, namespace: &str) -> Result<Self, LoadError> {
+        Self::try_load(dir.join(format!("{namespace}.grahh")))
+    }
+}
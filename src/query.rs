@@ -0,0 +1,35 @@
+use std::{collections::HashSet, hash::BuildHasher};
+
+use crate::{Database, Key, NodeHasher};
+
+/// multi-hop traversal over the database, each `follow` expands the whole frontier by one kind
+#[derive(Debug, Clone)]
+pub struct Query<'db, S = NodeHasher> {
+    db: &'db Database<S>,
+    frontier: HashSet<Key>,
+}
+
+impl<'db, S: BuildHasher> Query<'db, S> {
+    pub(crate) fn new(db: &'db Database<S>) -> Self {
+        Self {
+            db,
+            frontier: HashSet::new(),
+        }
+    }
+    /// adds `key` to the starting frontier, can be called more than once to start from several nodes
+    pub fn starting_at(mut self, key: Key) -> Self {
+        self.frontier.insert(key);
+        self
+    }
+    pub fn follow(mut self, kind: &str) -> Self {
+        self.frontier = self
+            .frontier
+            .iter()
+            .flat_map(|key| self.db.select(key, kind).iter().copied())
+            .collect();
+        self
+    }
+    pub fn collect(self) -> HashSet<Key> {
+        self.frontier
+    }
+}
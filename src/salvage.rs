@@ -0,0 +1,125 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use rkyv::util::AlignedVec;
+use thiserror::Error;
+
+use crate::{Database, Key, LoadError, Node, Storage, blob, lazy, snapshot, suffixed};
+
+/// something `Database::load_salvage` couldn't recover
+#[derive(Error, Debug)]
+pub enum SalvageError {
+    #[error("nothing could be read from {}", path.display())]
+    File {
+        path: PathBuf,
+        #[source]
+        source: LoadError,
+    },
+    #[error("{key} is damaged")]
+    Node {
+        key: Key,
+        #[source]
+        source: LoadError,
+    },
+    #[error("{key} has its value in blob {blob}, which can't be read")]
+    Blob {
+        key: Key,
+        blob: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("index ends {missing} nodes early, their keys are lost with them")]
+    Index { missing: u64 },
+}
+
+/// every node of the file at `path` that can be read, values that were stored out of line
+/// are read from `blobs`
+fn salvage_file(
+    path: &Path,
+    blobs: &Path,
+    lost: &mut Vec<SalvageError>,
+) -> Result<HashMap<Key, Node>, LoadError> {
+    let mut bytes: AlignedVec = AlignedVec::new();
+    bytes.extend_from_reader(&mut File::open(path)?)?;
+    if bytes.is_empty() {
+        return Ok(HashMap::new());
+    }
+    if lazy::is_indexed(&bytes) {
+        return lazy::salvage(&bytes, lost);
+    }
+    // the snapshot is one archive, if it doesn't validate there's no telling where any node is
+    let mut nodes: HashMap<Key, Node> = snapshot::deserialize(snapshot::access(&bytes)?)?;
+    nodes.retain(|key, node| {
+        let Some(blob) = &node.value.blob else {
+            return true;
+        };
+        match fs::read(blobs.join(blob)) {
+            Ok(bytes) => {
+                node.value.bytes = bytes;
+                true
+            }
+            Err(source) => {
+                lost.push(SalvageError::Blob {
+                    key: *key,
+                    blob: blob.clone(),
+                    source,
+                });
+                false
+            }
+        }
+    });
+    Ok(nodes)
+}
+
+impl Database {
+    /// `load` for a damaged file, returns every node that could be recovered along with
+    /// what couldn't. a snapshot written by `save` is one archive so it's all or nothing,
+    /// if it can't be read the backups from `set_backups` are tried newest first. nodes
+    /// whose blob is gone are dropped on their own, and a file written by `save_indexed`
+    /// loses only the nodes that are damaged. connections to dropped nodes are removed. the
+    /// database is backed by `path` like with `load`, so saving replaces the damaged file,
+    /// copy it somewhere first to keep it. zstd compressed files aren't supported
+    pub fn load_salvage(path: PathBuf) -> (Self, Vec<SalvageError>) {
+        if let Ok(db) = Self::try_load(path.clone()) {
+            return (db, Vec::new());
+        }
+        let blobs = blob::dir(&path);
+        let mut lost: Vec<SalvageError> = Vec::new();
+        let mut nodes: HashMap<Key, Node> = HashMap::new();
+        let backups = (1..).map(|n| suffixed(&path, &format!(".{n}")));
+        for candidate in [path.clone()].into_iter().chain(backups) {
+            if candidate != path && !candidate.is_file() {
+                break;
+            }
+            match salvage_file(&candidate, &blobs, &mut lost) {
+                Ok(salvaged) => {
+                    nodes = salvaged;
+                    break;
+                }
+                Err(source) => lost.push(SalvageError::File {
+                    path: candidate,
+                    source,
+                }),
+            }
+        }
+        let mut db = Self::in_memory();
+        db.storage = Storage::File(path);
+        let dangling: HashSet<Key> = nodes
+            .values()
+            .flat_map(Node::neighbors)
+            .filter(|neighbor| !nodes.contains_key(neighbor))
+            .collect();
+        for node in nodes.values_mut() {
+            node.remove_connections(&dangling);
+        }
+        // left dirty since the nodes aren't what's in the file anymore
+        db.inner.extend(nodes);
+        db.reserve_keys();
+        db.rebuild_indexes();
+        (db, lost)
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    mem,
+    ops::Deref,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+};
+
+use crate::{Database, SaveOnDrop};
+
+/// database that can be shared between threads
+///
+/// readers take a `Snapshot` of the current generation and are never blocked by writers,
+/// writers copy the current generation, change the copy and swap it in as the next one.
+/// every `write` copies the whole graph so batch changes into one call
+#[derive(Debug)]
+pub struct SharedDatabase {
+    current: RwLock<Arc<Database>>,
+    /// only one writer at a time, otherwise two writers copying the same generation
+    /// would overwrite each other's changes
+    writer: Mutex<()>,
+    /// taken from the database so only the latest generation is saved, when this is dropped
+    save_on_drop: SaveOnDrop,
+}
+
+/// read-only view of a single generation of a `SharedDatabase`, later writes don't show up in it
+#[derive(Debug, Clone)]
+pub struct Snapshot(Arc<Database>);
+
+impl Deref for Snapshot {
+    type Target = Database;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl SharedDatabase {
+    /// the `Database::set_save_on_drop` of `db` moves to the shared database, which saves
+    /// whatever generation is current when it's dropped. snapshots still held then don't save
+    pub fn new(mut db: Database) -> Self {
+        let save_on_drop = mem::take(&mut db.save_on_drop.0);
+        Self {
+            current: RwLock::new(Arc::new(db)),
+            writer: Mutex::new(()),
+            save_on_drop,
+        }
+    }
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot(Arc::clone(&self.current.read().unwrap()))
+    }
+    pub fn write<R>(&self, f: impl FnOnce(&mut Database) -> R) -> R {
+        let _writer = self.writer.lock().unwrap();
+        let mut next = Database::clone(&self.snapshot());
+        let result = f(&mut next);
+        *self.current.write().unwrap() = Arc::new(next);
+        result
+    }
+    pub fn save(&self) {
+        self.snapshot().save();
+    }
+}
+
+impl Drop for SharedDatabase {
+    fn drop(&mut self) {
+        let current = self
+            .current
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        current.save_for_drop(self.save_on_drop);
+    }
+}
@@ -0,0 +1,104 @@
+//! the database file `save` writes: a 16 byte header of `MAGIC`, the format version as a
+//! little endian u32 and 4 reserved bytes, then the rkyv archive of the node map. the header
+//! is 16 bytes so the archive after it stays aligned. files from before the header are the
+//! archive alone, the ones written by the first release are migrated when they're loaded
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::BuildHasher,
+    io::{Read, Write},
+};
+
+use rkyv::{collections::swiss_table::ArchivedHashMap, rancor, util::AlignedVec};
+
+use crate::{ArchivedKey, ArchivedNode, Key, LoadError, Node, SaveError, Value, blob::StoredNode};
+
+const MAGIC: &[u8; 8] = b"grahhdb\0";
+/// 1 added the header
+const VERSION: u32 = 1;
+const HEADER: usize = 16;
+
+/// values of the first release, the bytes of a value without a type tag
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(crate) struct LegacyValue(Vec<u8>);
+
+/// nodes of the first release, the map the file held is keyed the same way
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(crate) struct LegacyNode {
+    value: LegacyValue,
+    connections: HashMap<String, HashSet<Key>>,
+}
+
+impl From<LegacyNode> for Node {
+    fn from(legacy: LegacyNode) -> Self {
+        let mut node = Node::from_value(Value::from_bytes(legacy.value.0));
+        for (kind, keys) in legacy.connections {
+            node.connections.insert(kind, keys.into_iter().collect());
+        }
+        node
+    }
+}
+
+/// the node map of a database file, validated but not deserialized
+pub(crate) enum Contents<'a> {
+    Nodes(&'a ArchivedHashMap<ArchivedKey, ArchivedNode>),
+    /// a file from the first release, before the header
+    Legacy(&'a ArchivedHashMap<ArchivedKey, ArchivedLegacyNode>),
+}
+
+/// checks the header and validates the archive after it. bytes without a header are only
+/// accepted if they're a file from the first release
+pub(crate) fn access(bytes: &[u8]) -> Result<Contents<'_>, LoadError> {
+    if bytes.len() < HEADER || !bytes.starts_with(MAGIC) {
+        let legacy = rkyv::access::<_, rancor::Error>(bytes)?;
+        return Ok(Contents::Legacy(legacy));
+    }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    if version != VERSION {
+        return Err(LoadError::SnapshotVersion(version));
+    }
+    let nodes = rkyv::access::<_, rancor::Error>(&bytes[HEADER..])?;
+    Ok(Contents::Nodes(nodes))
+}
+
+pub(crate) fn deserialize<S: BuildHasher + Default>(
+    contents: Contents,
+) -> Result<HashMap<Key, Node, S>, rancor::Error> {
+    match contents {
+        Contents::Nodes(nodes) => rkyv::deserialize::<_, rancor::Error>(nodes),
+        Contents::Legacy(nodes) => {
+            let nodes: HashMap<Key, LegacyNode> = rkyv::deserialize::<_, rancor::Error>(nodes)?;
+            Ok(nodes
+                .into_iter()
+                .map(|(key, node)| (key, Node::from(node)))
+                .collect())
+        }
+    }
+}
+
+/// also returns how many bytes were read
+pub(crate) fn read<S: BuildHasher + Default>(
+    r: &mut impl Read,
+) -> Result<(HashMap<Key, Node, S>, usize), LoadError> {
+    let mut bytes: AlignedVec = AlignedVec::new();
+    bytes.extend_from_reader(r)?;
+    // a file that was created but never saved to is just an empty database
+    if bytes.is_empty() {
+        return Ok((HashMap::default(), 0));
+    }
+    let nodes = deserialize(access(&bytes)?)?;
+    Ok((nodes, bytes.len()))
+}
+
+/// returns how many bytes were written, the header included
+pub(crate) fn write(
+    stored: &HashMap<Key, StoredNode>,
+    w: &mut impl Write,
+) -> Result<usize, SaveError> {
+    let bytes = rkyv::to_bytes::<rancor::Error>(stored)?;
+    w.write_all(MAGIC)?;
+    w.write_all(&VERSION.to_le_bytes())?;
+    w.write_all(&[0; HEADER - 12])?;
+    w.write_all(&bytes)?;
+    Ok(HEADER + bytes.len())
+}
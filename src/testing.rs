@@ -0,0 +1,80 @@
+//! fixtures and assertions for tests against a `Database`, behind the `testing` feature
+
+use std::{collections::HashMap, hash::BuildHasher};
+
+use rand::{Rng, RngExt};
+
+use crate::{Database, Key, KeySet, Node};
+
+/// `nodes` nodes with their index as a `u64` value and up to `edges` `edge` connections between
+/// random pairs of them, pairs of a node with itself are skipped and pairs that come up twice
+/// are only connected once
+pub fn random_graph(nodes: usize, edges: usize, rng: &mut impl Rng) -> Database {
+    let mut db = Database::in_memory();
+    let keys: Vec<Key> = (0..nodes as u64).map(|i| db.create(&i)).collect();
+    if nodes < 2 {
+        return db;
+    }
+    for _ in 0..edges {
+        let first = keys[rng.random_range(0..nodes)];
+        let second = keys[rng.random_range(0..nodes)];
+        db.connect(first, "edge", second, "edge");
+    }
+    db
+}
+
+/// panics unless both databases have the same live nodes under the same keys, with the same
+/// values and connections. when connections were made and touched isn't compared, the
+/// message names the first key that differs
+#[track_caller]
+pub fn assert_graphs_equal<A: BuildHasher, B: BuildHasher>(a: &Database<A>, b: &Database<B>) {
+    let a = nodes(a);
+    let b = nodes(b);
+    for (key, node) in &a {
+        match b.get(key) {
+            Some(other) => assert_nodes_equal(key, node, other),
+            None => panic!("{key:?} is only in the first database"),
+        }
+    }
+    if let Some(key) = b.keys().find(|key| !a.contains_key(key)) {
+        panic!("{key:?} is only in the second database");
+    }
+}
+
+fn nodes<S: BuildHasher>(db: &Database<S>) -> HashMap<Key, &Node> {
+    db.iter().map(|(key, node)| (*key, node)).collect()
+}
+
+#[track_caller]
+fn assert_nodes_equal(key: &Key, a: &Node, b: &Node) {
+    assert!(a.value() == b.value(), "{key:?} has different values");
+    assert_same(key, "connections", &a.connections, &b.connections);
+    assert_same(key, "incoming connections", &a.incoming, &b.incoming);
+    assert_same(key, "weak connections", &a.weak, &b.weak);
+}
+
+/// ignores kinds with no nodes left in them, removing the last connection of a kind leaves
+/// an empty set behind until `shrink_to_fit`
+#[track_caller]
+fn assert_same(key: &Key, what: &str, a: &HashMap<String, KeySet>, b: &HashMap<String, KeySet>) {
+    let kinds = |map: &HashMap<String, KeySet>| -> Vec<String> {
+        let mut kinds: Vec<String> = map
+            .iter()
+            .filter(|(_kind, nodes)| !nodes.is_empty())
+            .map(|(kind, _nodes)| kind.clone())
+            .collect();
+        kinds.sort_unstable();
+        kinds
+    };
+    assert_eq!(
+        kinds(a),
+        kinds(b),
+        "{key:?} has {what} under different kinds"
+    );
+    for kind in kinds(a) {
+        assert_eq!(
+            a[&kind], b[&kind],
+            "{key:?} has different {what} under {kind}"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    hash::BuildHasher,
+};
+
+use crate::{Database, Key, Node};
+
+/// what a `Visitor` wants `Database::walk` to do next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    /// from `visit_node` the node's connections aren't followed, from `visit_edge` that one
+    /// connection isn't
+    Prune,
+    /// ends the walk
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    BreadthFirst,
+    DepthFirst,
+}
+
+/// callbacks for `Database::walk`, both default to `Control::Continue`
+pub trait Visitor {
+    /// called once per node, the first time the walk reaches it
+    fn visit_node(&mut self, _key: &Key, _node: &Node) -> Control {
+        Control::Continue
+    }
+    /// called for every connection out of a visited node, including ones back to nodes that
+    /// were already visited
+    fn visit_edge(&mut self, _from: &Key, _kind: &str, _to: &Key) -> Control {
+        Control::Continue
+    }
+}
+
+impl<S: BuildHasher> Database<S> {
+    /// visits everything reachable from `start` over connections of any kind in `order`
+    pub fn walk<V: Visitor>(&self, start: &Key, order: Order, visitor: &mut V) {
+        let mut visited: HashSet<Key> = HashSet::new();
+        let mut pending: VecDeque<Key> = VecDeque::from([*start]);
+        loop {
+            let next = match order {
+                Order::BreadthFirst => pending.pop_front(),
+                Order::DepthFirst => pending.pop_back(),
+            };
+            let Some(key) = next else {
+                return;
+            };
+            let Some(node) = self.get(&key) else {
+                continue;
+            };
+            if !visited.insert(key) {
+                continue;
+            }
+            match visitor.visit_node(&key, node) {
+                Control::Continue => {}
+                Control::Prune => continue,
+                Control::Stop => return,
+            }
+            for (kind, target) in node.edges() {
+                match visitor.visit_edge(&key, kind, target) {
+                    Control::Continue if !visited.contains(target) => pending.push_back(*target),
+                    Control::Continue | Control::Prune => {}
+                    Control::Stop => return,
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use grahh_db::Database;
+
+#[test]
+fn top_by_degree() {
+    let mut db = Database::in_memory();
+    let [hub, a, b, c, lonely] = [(); 5].map(|()| db.create(&()));
+    assert_eq!(db.connect_star(hub, "friend", [a, b, c]), 3);
+    assert!(db.connect(a, "coworker", b, "coworker"));
+    assert_eq!(db.top_by_degree(1), [(hub, 3)]);
+    // `a` and `b` tie so the older key comes first
+    assert_eq!(db.top_by_degree(3), [(hub, 3), (a, 2), (b, 2)]);
+    assert_eq!(
+        db.top_by_degree(10),
+        [(hub, 3), (a, 2), (b, 2), (c, 1), (lonely, 0)]
+    );
+    assert!(db.top_by_degree(0).is_empty());
+}
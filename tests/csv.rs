@@ -0,0 +1,59 @@
+use grahh_db::{Database, ImportError, Key};
+
+fn key(key: u64) -> Key {
+    Key::parse(&key.to_string()).unwrap()
+}
+
+#[test]
+fn quoted_fields() {
+    let mut db = Database::in_memory();
+    let csv = "1,\"best, \"\"oldest\"\" friend\",2\n\"3\",\"line\nbreak\",4\n";
+    assert_eq!(db.import_csv_edges(csv, "friend").unwrap(), 2);
+    assert!(
+        db.select(&key(1), "best, \"oldest\" friend")
+            .contains(&key(2))
+    );
+    assert!(db.select(&key(3), "line\nbreak").contains(&key(4)));
+}
+
+#[test]
+fn crlf_and_blank_lines() {
+    let mut db = Database::in_memory();
+    let csv = "source,kind,target\r\n1,,2\r\n\r\n2,likes,3\r\n";
+    assert_eq!(db.import_csv_edges(csv, "friend").unwrap(), 2);
+    assert!(db.select(&key(1), "friend").contains(&key(2)));
+    assert!(db.select(&key(2), "likes").contains(&key(3)));
+    assert!(db.select(&key(2), "likes\r").is_empty());
+}
+
+#[test]
+fn round_trip() {
+    let mut db = Database::in_memory();
+    let [a, b, c] = [(); 3].map(|()| db.create(&()));
+    assert!(db.connect(a, "says \"hi\", then", b, "says \"hi\", then"));
+    assert!(db.connect(b, "friend", c, "friend"));
+    let mut imported = Database::in_memory();
+    imported.import_csv_edges(&db.to_csv_edges(), "").unwrap();
+    assert!(imported.select(&a, "says \"hi\", then").contains(&b));
+    assert!(imported.select(&c, "friend").contains(&b));
+}
+
+#[test]
+fn malformed_rows() {
+    let mut db = Database::in_memory();
+    let unclosed = "1,friend,2\n3,\"friend,4\n5,friend,6\n";
+    assert!(matches!(
+        db.import_csv_edges(unclosed, "friend"),
+        Err(ImportError::Quote { line: 2 })
+    ));
+    assert!(matches!(
+        db.import_csv_edges("1,friend,2\n\"a\nb\",friend\n", "friend"),
+        Err(ImportError::Columns { line: 2, found: 2 })
+    ));
+    assert!(matches!(
+        db.import_csv_edges("1,friend,2\n\"3\n\",friend,x\n", "friend"),
+        Err(ImportError::Key { line: 2, .. })
+    ));
+    // nothing from the rows before is imported
+    assert!(db.is_empty());
+}
@@ -0,0 +1,60 @@
+use grahh_db::{Database, Directionality};
+
+#[test]
+fn duplicate_values_of_different_types() {
+    let mut db = Database::in_memory();
+    let first = db.create(&1u32);
+    let bytes = db.create(&[1u8, 0, 0, 0]);
+    let raw = db.create(&());
+    let raw_bytes = db.get_value(&first).unwrap().as_bytes().unwrap().to_vec();
+    assert!(db.set_value_bytes(raw, raw_bytes));
+    let second = db.create(&1u32);
+    assert_eq!(db.duplicate_values(), vec![vec![first, second]]);
+    assert!(db.get(&bytes).is_some());
+}
+
+#[test]
+fn dedup_merges_into_oldest() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let oldest = db.create(&1u8);
+    let duplicate = db.create(&1u8);
+    let other = db.create(&2u8);
+    let friend = db.create(&3u8);
+    assert!(db.connect(oldest, "friend", duplicate, "friend"));
+    assert!(db.connect(duplicate, "friend", friend, "friend"));
+    assert!(db.connect(other, "follows", duplicate, "follows"));
+    assert!(db.connect_weak(other, "saw", duplicate));
+
+    assert_eq!(db.dedup_values(), 1);
+    assert!(db.get(&duplicate).is_none());
+    assert_eq!(db.len(), 3);
+    let merged = db.select(&oldest, "friend");
+    assert_eq!(merged.len(), 1);
+    assert!(merged.contains(&friend));
+    assert!(db.select(&friend, "friend").contains(&oldest));
+    assert!(db.select(&other, "follows").contains(&oldest));
+    assert!(
+        db.get(&oldest)
+            .unwrap()
+            .get_incoming("follows")
+            .contains(&other)
+    );
+    assert_eq!(db.select_weak(&other, "saw").collect::<Vec<_>>(), [oldest]);
+    assert!(db.duplicate_values().is_empty());
+    assert_eq!(db.dedup_values(), 0);
+}
+
+#[test]
+fn dedup_with_soft_removed_neighbor() {
+    let mut db = Database::in_memory();
+    let oldest = db.create(&1u8);
+    let duplicate = db.create(&1u8);
+    let removed = db.create(&2u8);
+    assert!(db.connect(removed, "friend", duplicate, "friend"));
+    assert!(db.soft_remove(&removed));
+    assert_eq!(db.dedup_values(), 1);
+    assert!(db.restore(&removed));
+    assert!(db.select(&removed, "friend").contains(&oldest));
+    assert!(db.select(&oldest, "friend").contains(&removed));
+}
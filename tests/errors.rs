@@ -0,0 +1,221 @@
+use std::{fs, io};
+
+use grahh_db::{
+    CreateError, Database, DeserializeError, InvalidValue, Key, LoadError, MultiDatabase, Node,
+    SaveError, StorageBackend, Value,
+};
+
+/// fails every save and load
+#[derive(Debug)]
+struct Broken;
+
+impl StorageBackend for Broken {
+    fn save(&self, _bytes: &[u8]) -> io::Result<()> {
+        Err(io::Error::other("broken"))
+    }
+    fn load(&self) -> io::Result<Option<Vec<u8>>> {
+        Err(io::Error::other("broken"))
+    }
+}
+
+fn limited() -> Database {
+    let mut db = Database::in_memory();
+    db.set_max_value_bytes(Some(8));
+    db
+}
+
+const TOO_LARGE: &str = "this is more than eight bytes";
+
+#[test]
+fn create_too_large() {
+    let mut db = limited();
+    assert!(matches!(
+        db.try_create(&TOO_LARGE.to_owned()),
+        Err(CreateError::Invalid(InvalidValue::TooLarge(_)))
+    ));
+    assert!(db.try_insert(&TOO_LARGE.to_owned()).is_err());
+    assert!(
+        db.try_get_or_create(&TOO_LARGE.to_owned(), |_value| false)
+            .is_err()
+    );
+    assert_eq!(db.iter().count(), 0);
+}
+
+#[test]
+fn create_many_checks_everything_first() {
+    let mut db = limited();
+    let values = ["a".to_owned(), TOO_LARGE.to_owned()];
+    assert!(matches!(
+        db.try_create_many(&values),
+        Err(CreateError::Invalid(_))
+    ));
+    assert_eq!(db.iter().count(), 0);
+}
+
+#[test]
+fn create_with_key() {
+    let mut db = limited();
+    let key = Key::generate();
+    assert!(matches!(
+        db.try_create_with_key(key, &TOO_LARGE.to_owned()),
+        Err(CreateError::Invalid(_))
+    ));
+    db.try_create_with_key(key, &()).unwrap();
+    assert!(matches!(
+        db.try_create_with_key(key, &()),
+        Err(CreateError::Exists(_))
+    ));
+}
+
+#[test]
+fn update_too_large() {
+    let mut db = Database::in_memory();
+    let key = db.create(&vec![1u64]);
+    let len = db.get(&key).unwrap().value().len();
+    db.set_max_value_bytes(Some(len));
+    assert!(db.try_update(&key, &TOO_LARGE.to_owned()).is_err());
+    assert!(db.try_push_to_value(key, &2u64).is_err());
+    assert!(
+        db.entry(Key::generate())
+            .try_or_insert(&TOO_LARGE.to_owned())
+            .is_err()
+    );
+    assert!(
+        db.try_replace_node(key, Node::new(&TOO_LARGE.to_owned()))
+            .is_err()
+    );
+    assert!(db.entry(key).try_and_update(&TOO_LARGE.to_owned()).is_err());
+    assert_eq!(db.get(&key).unwrap().value().len(), len);
+    db.entry(key).and_update(&vec![2u64]);
+    assert_eq!(
+        db.get(&key)
+            .unwrap()
+            .value()
+            .deserialize_owned::<Vec<u64>>(),
+        Some(vec![2])
+    );
+}
+
+#[test]
+fn create_when_full() {
+    let mut db = Database::builder().max_nodes(1).build();
+    let key = db.create(&1u8);
+    assert!(matches!(db.try_create(&2u8), Err(CreateError::Full(_))));
+    assert!(matches!(
+        db.try_create_with_key(Key::generate(), &2u8),
+        Err(CreateError::Full(_))
+    ));
+    assert!(db.entry(Key::generate()).try_or_insert(&2u8).is_err());
+    assert!(db.try_replace_node(key, Node::new(&2u8)).is_ok());
+    assert_eq!(db.len(), 1);
+
+    // room for one more but not both
+    let mut db = Database::builder().max_nodes(2).build();
+    db.create(&1u8);
+    assert!(matches!(
+        db.try_create_many(&[2u8, 3u8]),
+        Err(CreateError::Full(_))
+    ));
+    assert_eq!(db.len(), 1);
+}
+
+/// the same type before and after being moved to another module
+mod before {
+    #[derive(rkyv::Archive, rkyv::Serialize)]
+    pub struct Point {
+        pub x: u32,
+        pub y: u32,
+    }
+}
+
+mod after {
+    #[derive(rkyv::Archive, rkyv::Serialize)]
+    pub struct Point {
+        pub x: u32,
+        pub y: u32,
+    }
+}
+
+#[test]
+fn deserialize_renamed_type() {
+    let value = Value::serialize(&before::Point { x: 1, y: 2 });
+    assert!(matches!(
+        value.try_deserialize::<after::ArchivedPoint>(),
+        Err(DeserializeError::TypeMismatch)
+    ));
+    let point = value
+        .deserialize_untagged::<after::ArchivedPoint>()
+        .unwrap();
+    assert_eq!((point.x.to_native(), point.y.to_native()), (1, 2));
+
+    let copy = Value::from_bytes(value.as_bytes().unwrap().to_vec());
+    assert!(copy.deserialize::<after::ArchivedPoint>().is_some());
+    assert_eq!(copy, Value::serialize(&after::Point { x: 1, y: 2 }));
+}
+
+#[test]
+fn connect_to_itself() {
+    let mut db = Database::in_memory();
+    let key = db.create(&1u8);
+    assert!(!db.connect(key, "friend", key, "friend"));
+    assert!(!db.disconnect(&key, &key));
+    assert!(db.select(&key, "friend").is_empty());
+}
+
+#[test]
+fn save_to_missing_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let db = Database::load(path);
+    dir.close().unwrap();
+    assert!(matches!(db.try_save(), Err(SaveError::Io(_))));
+}
+
+#[test]
+fn load_invalid_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    fs::write(&path, b"not a database").unwrap();
+    assert!(matches!(
+        Database::try_load(path),
+        Err(LoadError::Invalid(_))
+    ));
+}
+
+#[test]
+fn load_missing_blob() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut db = Database::load(path.clone());
+    db.set_blob_threshold(Some(0));
+    db.create(&TOO_LARGE.to_owned());
+    db.save();
+    fs::remove_dir_all(dir.path().join("db.grahh.blobs")).unwrap();
+    assert!(matches!(Database::try_load(path), Err(LoadError::Io(_))));
+}
+
+#[test]
+fn broken_backend() {
+    assert!(matches!(
+        Database::try_load_backend(Broken),
+        Err(LoadError::Io(_))
+    ));
+}
+
+#[test]
+fn multi_database_invalid_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("broken.grahh"), b"not a database").unwrap();
+    assert!(matches!(
+        MultiDatabase::try_open(dir.path().to_owned()),
+        Err(LoadError::Invalid(_))
+    ));
+
+    let mut multi = MultiDatabase::try_open(dir.path().join("multi")).unwrap();
+    fs::write(dir.path().join("multi/later.grahh"), b"not a database").unwrap();
+    assert!(multi.try_get_or_create("later").is_err());
+    multi.try_get_or_create("fine").unwrap().create(&1u8);
+    multi.try_save_all().unwrap();
+    fs::remove_dir_all(dir.path().join("multi")).unwrap();
+    assert!(matches!(multi.try_save_all(), Err(SaveError::Io(_))));
+}
@@ -0,0 +1,115 @@
+#[cfg(feature = "petgraph")]
+use grahh_db::Directionality;
+use grahh_db::{Database, Key, Value};
+use rkyv::string::ArchivedString;
+
+fn key(key: u64) -> Key {
+    Key::parse(&key.to_string()).unwrap()
+}
+
+/// the jsonl line of a node with `bytes` as its value
+fn jsonl(bytes: &[u8]) -> String {
+    let mut db = Database::in_memory();
+    db.create_with_key(key(1), &()).unwrap();
+    assert!(db.set_value_bytes(key(1), bytes.to_vec()));
+    let mut out = Vec::new();
+    db.write_jsonl(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn jsonl_base64() {
+    for (bytes, base64) in [
+        (&b""[..], ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg=="),
+        (b"fooba", "Zm9vYmE="),
+        (b"foobar", "Zm9vYmFy"),
+        (&[0xff, 0xfe, 0x00], "//4A"),
+    ] {
+        assert_eq!(
+            jsonl(bytes),
+            format!("{{\"key\":\"1\",\"value\":\"{base64}\",\"connections\":{{}}}}\n")
+        );
+    }
+}
+
+#[test]
+fn jsonl_connections() {
+    let mut db = Database::in_memory();
+    for n in 1..=3 {
+        db.create_with_key(key(n), &()).unwrap();
+        assert!(db.set_value_bytes(key(n), Vec::new()));
+    }
+    assert!(db.connect(key(1), "says \"hi\"", key(3), "x"));
+    assert!(db.connect(key(1), "friend", key(2), "friend"));
+    let mut out = Vec::new();
+    db.write_jsonl(&mut out).unwrap();
+    let lines: Vec<&str> = std::str::from_utf8(&out).unwrap().lines().collect();
+    assert_eq!(
+        lines,
+        [
+            r#"{"key":"1","value":"","connections":{"friend":["2"],"says \"hi\"":["3"]}}"#,
+            r#"{"key":"2","value":"","connections":{"friend":["1"]}}"#,
+            r#"{"key":"3","value":"","connections":{"x":["1"]}}"#,
+        ]
+    );
+}
+
+#[test]
+fn ntriples_escaping() {
+    let mut db = Database::in_memory();
+    db.create_with_key(key(1), &"say \"hi\"\\\nbye\r".to_owned())
+        .unwrap();
+    db.create_with_key(key(2), &String::new()).unwrap();
+    assert!(db.connect(key(1), "best friend>", key(2), "best friend>"));
+    let text = |value: &Value| {
+        value
+            .deserialize::<ArchivedString>()
+            .map(|text| text.to_string())
+    };
+    let mut lines: Vec<String> = db
+        .to_ntriples_with(text)
+        .lines()
+        .map(str::to_owned)
+        .collect();
+    lines.sort_unstable();
+    assert_eq!(
+        lines,
+        [
+            r#"<urn:grahh:key:1> <urn:grahh:kind:best%20friend%3E> <urn:grahh:key:2> ."#,
+            r#"<urn:grahh:key:1> <urn:grahh:value> "say \"hi\"\\\nbye\r" ."#,
+            r#"<urn:grahh:key:2> <urn:grahh:kind:best%20friend%3E> <urn:grahh:key:1> ."#,
+            r#"<urn:grahh:key:2> <urn:grahh:value> "" ."#,
+        ]
+    );
+}
+
+#[cfg(feature = "petgraph")]
+#[test]
+fn to_petgraph() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let [a, b, c, removed] = [(); 4].map(|()| db.create(&()));
+    assert!(db.connect(a, "follows", b, "follows"));
+    assert!(db.connect(b, "follows", c, "follows"));
+    assert!(db.connect(a, "friend", b, "friend"));
+    assert!(db.connect(c, "follows", removed, "follows"));
+    assert!(db.soft_remove(&removed));
+
+    let (graph, index) = db.to_petgraph("follows");
+    assert_eq!(graph.node_count(), 3);
+    assert!(!index.contains_key(&removed));
+    assert_eq!(graph[index[&a]], a);
+    assert_eq!(graph.edge_count(), 2);
+    assert!(graph.find_edge(index[&a], index[&b]).is_some());
+    assert!(graph.find_edge(index[&b], index[&a]).is_none());
+    assert!(graph.find_edge(index[&b], index[&c]).is_some());
+
+    // undirected kinds are an edge each way
+    let (graph, index) = db.to_petgraph("friend");
+    assert_eq!(graph.edge_count(), 2);
+    assert!(graph.find_edge(index[&b], index[&a]).is_some());
+}
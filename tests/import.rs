@@ -0,0 +1,105 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use grahh_db::{Database, Directionality, Key, KeyGenerator};
+
+fn value(db: &Database, key: &Key) -> u8 {
+    *db.get(key).unwrap().value().deserialize::<u8>().unwrap()
+}
+
+/// hands out every key twice
+#[derive(Debug, Default)]
+struct Twice(AtomicU64);
+
+impl KeyGenerator for Twice {
+    fn generate(&self) -> Option<Key> {
+        let key = self.0.fetch_add(1, Ordering::Relaxed) / 2;
+        Some(Key::parse(&key.to_string()).unwrap())
+    }
+}
+
+#[test]
+fn import_into_non_empty() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut saved = Database::load(path.clone());
+    saved.declare_kind("follows", Directionality::Directed);
+    let [a, b, c, removed] = [1u8, 2, 3, 4].map(|value| saved.create(&value));
+    assert!(saved.connect(a, "friend", b, "friend"));
+    assert!(saved.connect(b, "follows", c, "follows"));
+    assert!(saved.connect(c, "friend", removed, "friend"));
+    assert!(saved.soft_remove(&removed));
+    saved.save();
+
+    let mut db = Database::builder().key_generator(Twice::default()).build();
+    db.declare_kind("follows", Directionality::Directed);
+    let existing = db.create(&0u8);
+    let keys = db.import_file(&path).unwrap();
+    assert_eq!(keys.len(), 3);
+    assert!(!keys.contains_key(&removed));
+    assert_eq!(db.len(), 4);
+    assert_eq!(value(&db, &existing), 0);
+    let (a, b, c) = (keys[&a], keys[&b], keys[&c]);
+    assert!(![a, b, c].contains(&existing));
+    assert_eq!([a, b, c].map(|key| value(&db, &key)), [1, 2, 3]);
+    assert!(db.select(&a, "friend").contains(&b));
+    assert!(db.select(&b, "friend").contains(&a));
+    assert!(db.select(&b, "follows").contains(&c));
+    assert!(db.get(&c).unwrap().get_incoming("follows").contains(&b));
+    assert!(db.select(&c, "friend").is_empty());
+}
+
+#[test]
+fn import_when_full() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut saved = Database::load(path.clone());
+    saved.create_many(&[1u8, 2]);
+    saved.save();
+
+    let mut db = Database::builder().max_nodes(2).build();
+    db.create(&0u8);
+    assert!(db.import_file(&path).is_err());
+    assert_eq!(db.len(), 1);
+}
+
+/// key of the node whose value is `value`
+#[cfg(feature = "petgraph")]
+fn find(db: &Database, value: u32) -> Key {
+    let (key, _node) = db
+        .iter()
+        .find(|(_key, node)| {
+            node.value()
+                .deserialize::<rkyv::Archived<u32>>()
+                .is_some_and(|archived| archived.to_native() == value)
+        })
+        .unwrap();
+    *key
+}
+
+#[cfg(feature = "petgraph")]
+#[test]
+fn from_petgraph() {
+    let mut graph = petgraph::Graph::<u32, ()>::new();
+    let [a, b, c] = [10, 20, 30].map(|weight| graph.add_node(weight));
+    for (from, to) in [(a, b), (b, c), (c, c), (a, b)] {
+        graph.add_edge(from, to, ());
+    }
+    let db = Database::from_petgraph(&graph, "next");
+    assert_eq!(db.len(), 3);
+    assert_eq!(db.kind("next"), Some(Directionality::Directed));
+    let [a, b, c] = [10, 20, 30].map(|value| find(&db, value));
+    assert_eq!(db.select(&a, "next").iter().collect::<Vec<_>>(), [&b]);
+    assert!(db.select(&b, "next").contains(&c));
+    assert_eq!(db.select(&b, "next").len(), 1);
+    assert!(db.select(&c, "next").is_empty());
+    assert!(db.get(&b).unwrap().get_incoming("next").contains(&a));
+
+    let mut graph = petgraph::graph::UnGraph::<u32, ()>::new_undirected();
+    let [a, b] = [10, 20].map(|weight| graph.add_node(weight));
+    graph.add_edge(a, b, ());
+    let db = Database::from_petgraph(&graph, "near");
+    assert_eq!(db.kind("near"), Some(Directionality::Undirected));
+    let [a, b] = [10, 20].map(|value| find(&db, value));
+    assert!(db.select(&a, "near").contains(&b));
+    assert!(db.select(&b, "near").contains(&a));
+}
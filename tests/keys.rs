@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use grahh_db::{CounterKeys, CreateError, Database, Key, KeyGenerator};
+
+fn key(key: u64) -> Key {
+    Key::parse(&key.to_string()).unwrap()
+}
+
+#[test]
+fn generate_near_max() {
+    let mut db = Database::builder()
+        .key_generator(CounterKeys::default())
+        .build();
+    db.create_with_key(key(u64::MAX - 2), &()).unwrap();
+    assert_eq!(db.create(&()), key(u64::MAX - 1));
+    assert_eq!(db.create(&()), key(u64::MAX));
+    assert!(matches!(db.try_create(&()), Err(CreateError::OutOfKeys(_))));
+}
+
+#[test]
+fn timestamps_ignore_keys_far_ahead() {
+    let mut db = Database::in_memory();
+    let mut other = Database::in_memory();
+    let far = key(u64::MAX - 2);
+    db.create_with_key(far, &()).unwrap();
+    assert!(db.create(&()) < far);
+    assert!(other.create(&()) < far);
+    assert!(Key::generate() < far);
+}
+
+/// hands out every key twice
+#[derive(Debug, Default)]
+struct Twice(AtomicU64);
+
+impl KeyGenerator for Twice {
+    fn generate(&self) -> Option<Key> {
+        Some(key(self.0.fetch_add(1, Ordering::Relaxed) / 2))
+    }
+}
+
+#[test]
+fn retry_taken_keys() {
+    let mut db = Database::builder().key_generator(Twice::default()).build();
+    assert_eq!(db.create_many(&[1u8, 2, 3]), [key(0), key(1), key(2)]);
+}
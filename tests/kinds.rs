@@ -0,0 +1,264 @@
+use grahh_db::{Database, Directionality, Key, KindMarker, define_kinds};
+
+define_kinds! {
+    /// kinds used by the tests
+    pub enum Kinds {
+        Friend = "friend",
+        /// only goes one way
+        Follows = "follows",
+    }
+}
+
+#[test]
+fn strings() {
+    assert_eq!(Kinds::Friend.as_str(), "friend");
+    assert_eq!(Kinds::Follows.to_string(), "follows");
+    assert_eq!(String::from(Kinds::Friend), "friend");
+    assert_eq!(&*Kinds::Follows, "follows");
+    assert_eq!(Kinds::ALL, &[Kinds::Friend, Kinds::Follows]);
+}
+
+#[test]
+fn parse() {
+    for &kind in Kinds::ALL {
+        assert_eq!(Kinds::parse(kind.as_str()), Some(kind));
+    }
+    assert_eq!(Kinds::parse("freind"), None);
+}
+
+#[test]
+fn connect_with_kinds() {
+    let mut db = Database::in_memory();
+    db.declare_kind(Kinds::Follows, Directionality::Directed);
+    let a = db.create(&1_u32);
+    let b = db.create(&2_u32);
+    assert!(db.connect(a, Kinds::Friend, b, Kinds::Friend));
+    assert!(db.connect(a, Kinds::Follows, b, Kinds::Follows));
+    assert!(db.select(&b, &Kinds::Friend).contains(&a));
+    assert!(db.select(&a, &Kinds::Follows).contains(&b));
+    assert!(db.select(&b, &Kinds::Follows).is_empty());
+    // the generated kinds are the same strings as writing them out
+    assert!(db.select(&a, "friend").contains(&b));
+}
+
+struct Likes;
+
+impl KindMarker for Likes {
+    const NAME: &'static str = "likes";
+    const DIRECTED: bool = true;
+}
+
+struct Knows;
+
+impl KindMarker for Knows {
+    const NAME: &'static str = "knows";
+    const DIRECTED: bool = false;
+}
+
+#[test]
+fn connect_typed() {
+    let mut db = Database::in_memory();
+    let a = db.create(&1_u32);
+    let b = db.create(&2_u32);
+    assert!(db.connect_typed::<Likes>(a, b));
+    assert!(db.connect_typed::<Knows>(a, b));
+    assert_eq!(db.kind("likes"), Some(Directionality::Directed));
+    assert_eq!(db.kind("knows"), Some(Directionality::Undirected));
+    assert!(db.select_typed::<Likes>(&a).contains(&b));
+    assert!(db.select_typed::<Likes>(&b).is_empty());
+    assert!(db.select_typed::<Knows>(&b).contains(&a));
+}
+
+#[test]
+fn connect_typed_declared_the_other_way() {
+    let mut db = Database::in_memory();
+    db.declare_kind("likes", Directionality::Undirected);
+    let a = db.create(&1_u32);
+    let b = db.create(&2_u32);
+    assert!(!db.connect_typed::<Likes>(a, b));
+    assert!(db.select(&a, "likes").is_empty());
+}
+
+#[test]
+fn connect_directed_to_another_kind() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let a = db.create(&1_u32);
+    let b = db.create(&2_u32);
+    let c = db.create(&3_u32);
+    assert!(db.connect(a, "follows", b, "followed_by"));
+    assert!(db.connect(b, "follows", c, "followed_by"));
+    assert!(db.get(&b).unwrap().get_incoming("follows").contains(&a));
+    assert_eq!(
+        db.shortest_path_bidirectional(&a, &c, "follows"),
+        Some(vec![a, b, c])
+    );
+    assert!(db.reachable(&a, &c, "follows"));
+    assert_eq!(db.edges_between(&[a].into(), &[b].into(), "follows"), 1);
+
+    let before: Vec<Key> = db
+        .get(&b)
+        .unwrap()
+        .get_incoming("follows")
+        .iter()
+        .copied()
+        .collect();
+    db.rebuild_indexes();
+    let after: Vec<Key> = db
+        .get(&b)
+        .unwrap()
+        .get_incoming("follows")
+        .iter()
+        .copied()
+        .collect();
+    assert_eq!(before, after);
+}
+
+#[test]
+fn shrink_to_fit_drops_empty_incoming() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let a = db.create(&1_u32);
+    let b = db.create(&2_u32);
+    let fresh = db.get(&b).unwrap().clone();
+    assert!(db.connect(a, "follows", b, "follows"));
+    assert!(db.disconnect(&a, &b));
+    assert_ne!(db.get(&b), Some(&fresh));
+    db.shrink_to_fit();
+    assert_eq!(db.get(&b), Some(&fresh));
+}
+
+#[test]
+fn disconnect_kind_all_pairs() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let parent = db.create(&1_u32);
+    let child = db.create(&2_u32);
+    let friend = db.create(&3_u32);
+    assert!(db.connect(parent, "child", child, "parent"));
+    assert!(db.connect(parent, "follows", child, "follows"));
+    assert!(db.connect(parent, "friend", friend, "friend"));
+    assert!(db.connect(parent, "follows", friend, "follows"));
+
+    assert_eq!(db.disconnect_kind_all(&parent, "child"), 1);
+    assert!(db.select(&parent, "child").is_empty());
+    // still followed by the parent, so only `child` is gone from its side
+    assert!(
+        db.get(&child)
+            .unwrap()
+            .get_incoming("follows")
+            .contains(&parent)
+    );
+    assert_eq!(db.disconnect_kind_all(&parent, "follows"), 2);
+    assert!(db.get(&child).unwrap().get_incoming("follows").is_empty());
+    assert!(db.get(&friend).unwrap().get_incoming("follows").is_empty());
+    assert!(db.select(&friend, "friend").contains(&parent));
+
+    assert!(db.connect(parent, "child", child, "parent"));
+    assert_eq!(db.disconnect_kind_all(&parent, "child"), 1);
+    assert!(db.select(&child, "parent").is_empty());
+}
+
+#[test]
+fn declare_directed_after_connecting() {
+    let mut db = Database::in_memory();
+    let [a, b, c] = [(); 3].map(|()| db.create(&()));
+    assert!(db.connect(a, "follows", b, "followed"));
+    assert!(db.connect(b, "follows", c, "followed"));
+    db.declare_kind("follows", Directionality::Directed);
+    assert!(db.get(&c).unwrap().get_incoming("follows").contains(&b));
+    assert!(db.reachable(&a, &c, "follows"));
+    assert_eq!(
+        db.shortest_path_bidirectional(&a, &c, "follows"),
+        Some(vec![a, b, c])
+    );
+    // declaring it again leaves the index alone
+    db.declare_kind("follows", Directionality::Directed);
+    assert_eq!(db.get(&c).unwrap().get_incoming("follows").len(), 1);
+}
+
+#[test]
+fn weak_connections() {
+    let mut db = Database::in_memory();
+    let [fan, other, hub] = [(); 3].map(|()| db.create(&()));
+    assert!(db.connect_weak(fan, "likes", hub));
+    assert!(db.connect_weak(other, "likes", hub));
+    assert!(!db.connect_weak(fan, "likes", fan));
+    assert!(!db.connect_weak(fan, "likes", Key::generate()));
+    assert_eq!(db.select_weak(&fan, "likes").collect::<Vec<_>>(), [hub]);
+    // only the side that made them knows about them
+    assert!(db.get(&hub).unwrap().is_isolated());
+    assert!(db.select(&fan, "likes").is_empty());
+
+    assert!(db.soft_remove(&hub));
+    assert_eq!(db.select_weak(&fan, "likes").count(), 0);
+    assert!(db.restore(&hub));
+    assert_eq!(db.select_weak(&fan, "likes").collect::<Vec<_>>(), [hub]);
+
+    assert!(db.disconnect_weak(&other, "likes", &hub));
+    assert!(!db.disconnect_weak(&other, "likes", &hub));
+    assert!(db.remove(hub).is_some());
+    assert_eq!(db.select_weak(&fan, "likes").count(), 0);
+    assert!(db.get(&fan).unwrap().get_weak("likes").contains(&hub));
+    assert_eq!(db.prune_weak(), 1);
+    assert!(db.get(&fan).unwrap().get_weak("likes").is_empty());
+}
+
+#[test]
+fn unique_targets() {
+    let mut db = Database::in_memory();
+    let [a, b, c] = [(); 3].map(|()| db.create(&()));
+    assert!(db.connect(a, "friend", b, "friend"));
+    assert!(db.connect(a, "coworker", b, "coworker"));
+    let mut kinds = db.get(&a).unwrap().connections_to(&b);
+    kinds.sort_unstable();
+    assert_eq!(kinds, ["coworker", "friend"]);
+    assert!(db.get(&a).unwrap().connections_to(&c).is_empty());
+
+    db.set_unique_targets(true);
+    assert!(db.connect(b, "friend", c, "friend"));
+    assert!(!db.connect(b, "rival", c, "rival"));
+    assert!(!db.connect(c, "rival", b, "rival"));
+    // the same kind again is fine, and so is what was there before turning it on
+    assert!(db.connect(b, "friend", c, "friend"));
+    assert_eq!(db.get(&a).unwrap().connections_to(&b).len(), 2);
+    assert_eq!(db.connect_star(c, "rival", [a, b]), 1);
+    assert_eq!(db.get(&c).unwrap().connections_to(&b), ["friend"]);
+
+    db.set_unique_targets(false);
+    assert!(db.connect(c, "rival", b, "rival"));
+}
+
+#[test]
+fn connect_star() {
+    let mut db = Database::in_memory();
+    db.declare_kind(Kinds::Follows, Directionality::Directed);
+    let center = db.create(&0u8);
+    let leaves: Vec<Key> = (1..=4u8).map(|value| db.create(&value)).collect();
+    let removed = db.create(&5u8);
+    assert!(db.soft_remove(&removed));
+    assert!(db.connect(center, "friend", leaves[0], "friend"));
+
+    let candidates = [
+        leaves.clone(),
+        vec![leaves[1], center, removed, Key::generate()],
+    ];
+    assert_eq!(db.connect_star(center, "friend", candidates.concat()), 3);
+    assert_eq!(db.select(&center, "friend").len(), 4);
+    assert!(
+        leaves
+            .iter()
+            .all(|leaf| db.select(leaf, "friend").contains(&center))
+    );
+
+    assert_eq!(db.connect_star(center, Kinds::Follows, leaves.clone()), 4);
+    assert!(leaves.iter().all(|leaf| {
+        db.select(leaf, &Kinds::Follows).is_empty()
+            && db
+                .get(leaf)
+                .unwrap()
+                .get_incoming(&Kinds::Follows)
+                .contains(&center)
+    }));
+    assert_eq!(db.connect_star(removed, "friend", leaves), 0);
+}
@@ -0,0 +1,77 @@
+use std::fs;
+
+use grahh_db::{Database, Directionality, Key, LazyDatabase, LoadError, Node};
+
+fn value(node: &Node) -> u8 {
+    *node.value().deserialize::<u8>().unwrap()
+}
+
+#[test]
+fn indexed_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahhidx");
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let [a, b, removed] = [1u8, 2, 3].map(|value| db.create(&value));
+    assert!(db.connect(a, "follows", b, "follows"));
+    assert!(db.connect(a, "friend", removed, "friend"));
+    assert!(db.soft_remove(&removed));
+    db.save_indexed(&path).unwrap();
+    // saving again replaces the file
+    db.save_indexed(&path).unwrap();
+    assert!(!dir.path().join("db.grahhidx.tmp").exists());
+
+    let lazy = LazyDatabase::open(&path).unwrap();
+    assert_eq!(lazy.len(), 2);
+    assert!(lazy.contains_key(&a));
+    assert!(!lazy.contains_key(&removed));
+    let mut keys = vec![a, b];
+    keys.sort_unstable();
+    assert_eq!(lazy.keys().collect::<Vec<_>>(), keys);
+    let node = lazy.get(&a).unwrap().unwrap();
+    assert_eq!(value(&node), 1);
+    assert!(node.get_connections("follows").contains(&b));
+    assert!(lazy.get(&removed).unwrap().is_none());
+    assert!(lazy.get(&Key::generate()).unwrap().is_none());
+
+    let mut loaded = lazy.to_database().unwrap();
+    loaded.declare_kind("follows", Directionality::Directed);
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(value(loaded.get(&b).unwrap()), 2);
+    assert!(loaded.get(&b).unwrap().get_incoming("follows").contains(&a));
+    assert!(loaded.select(&a, "friend").is_empty());
+}
+
+/// an indexed file with one entry per `(key, offset, len)` and `nodes` after the index
+fn indexed(entries: &[(u64, u64, u64)], nodes: &[u8]) -> Vec<u8> {
+    let mut bytes = b"grahhidx".to_vec();
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    bytes.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+    for (key, offset, len) in entries {
+        for number in [key, offset, len] {
+            bytes.extend_from_slice(&number.to_le_bytes());
+        }
+    }
+    bytes.extend_from_slice(nodes);
+    bytes
+}
+
+#[test]
+fn corrupt_index() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahhidx");
+    for bytes in [
+        indexed(&[(1, 0, u64::MAX)], &[0; 8]),
+        indexed(&[(1, u64::MAX, 1)], &[0; 8]),
+        indexed(&[(1, 4, 8)], &[0; 8]),
+        indexed(&[(2, 0, 4), (1, 4, 4)], &[0; 8]),
+        indexed(&[(1, 0, 4), (1, 4, 4)], &[0; 8]),
+    ] {
+        fs::write(&path, bytes).unwrap();
+        assert!(matches!(LazyDatabase::open(&path), Err(LoadError::Index)));
+    }
+    let mut count_too_large = indexed(&[], &[]);
+    count_too_large[12..20].copy_from_slice(&u64::MAX.to_le_bytes());
+    fs::write(&path, count_too_large).unwrap();
+    assert!(matches!(LazyDatabase::open(&path), Err(LoadError::Index)));
+}
@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+
+use grahh_db::{Control, Database, Directionality, Key, Node, Order, Visitor};
+
+/// checks `path` goes from `from` to `to` over `kind` connections
+fn assert_path(db: &Database, path: &[Key], from: Key, to: Key, kind: &str) {
+    assert_eq!(path.first(), Some(&from));
+    assert_eq!(path.last(), Some(&to));
+    for pair in path.windows(2) {
+        assert!(db.select(&pair[0], kind).contains(&pair[1]));
+    }
+}
+
+/// both searches find a path of the same length for every pair, or neither does
+fn assert_same_lengths(db: &Database, keys: &[Key], kind: &str) {
+    for &from in keys {
+        for &to in keys {
+            let forward = db.shortest_path(&from, &to, kind);
+            let bidirectional = db.shortest_path_bidirectional(&from, &to, kind);
+            assert_eq!(
+                forward.as_ref().map(Vec::len),
+                bidirectional.as_ref().map(Vec::len),
+                "{from} to {to}"
+            );
+            if let Some(path) = bidirectional {
+                assert_path(db, &path, from, to, kind);
+            }
+        }
+    }
+}
+
+#[test]
+fn undirected_grid() {
+    let mut db = Database::in_memory();
+    db.declare_kind("near", Directionality::Undirected);
+    let size = 6;
+    let keys: Vec<Key> = (0..size * size).map(|_| db.create(&())).collect();
+    for row in 0..size {
+        for column in 0..size {
+            let key = keys[row * size + column];
+            if column + 1 < size {
+                assert!(db.connect(key, "near", keys[row * size + column + 1], "near"));
+            }
+            if row + 1 < size {
+                assert!(db.connect(key, "near", keys[(row + 1) * size + column], "near"));
+            }
+        }
+    }
+    let corner = db.shortest_path_bidirectional(&keys[0], &keys[size * size - 1], "near");
+    assert_eq!(corner.map(|path| path.len()), Some(2 * size - 1));
+    assert_same_lengths(&db, &keys, "near");
+}
+
+#[test]
+fn directed_goes_backwards_over_incoming() {
+    let mut db = Database::in_memory();
+    db.declare_kind("next", Directionality::Directed);
+    let [a, b, c, d] = [(); 4].map(|()| db.create(&()));
+    for (from, to) in [(a, b), (b, c), (c, d), (a, c)] {
+        assert!(db.connect(from, "next", to, "next"));
+    }
+    assert_eq!(
+        db.shortest_path_bidirectional(&a, &d, "next"),
+        Some(vec![a, c, d])
+    );
+    assert_eq!(db.shortest_path_bidirectional(&d, &a, "next"), None);
+    assert_eq!(
+        db.shortest_path_bidirectional(&b, &b, "next"),
+        Some(vec![b])
+    );
+}
+
+#[test]
+fn directed_random_graph() {
+    let mut db = Database::in_memory();
+    db.declare_kind("next", Directionality::Directed);
+    let keys: Vec<Key> = (0..40).map(|_| db.create(&())).collect();
+    // a fixed linear congruential generator so every run checks the same graph
+    let mut state: u64 = 1;
+    let mut random = || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize % keys.len()
+    };
+    for _ in 0..80 {
+        let (from, to) = (random(), random());
+        db.connect(keys[from], "next", keys[to], "next");
+    }
+    assert_same_lengths(&db, &keys, "next");
+}
+
+#[test]
+fn skips_soft_removed_and_undeclared() {
+    let mut db = Database::in_memory();
+    db.declare_kind("near", Directionality::Undirected);
+    let [a, b, c] = [(); 3].map(|()| db.create(&()));
+    assert!(db.connect(a, "near", b, "near"));
+    assert!(db.connect(b, "near", c, "near"));
+    // undeclared kinds fall back to `shortest_path`
+    assert!(db.connect(a, "link", c, "link"));
+    assert_eq!(
+        db.shortest_path_bidirectional(&a, &c, "link"),
+        Some(vec![a, c])
+    );
+    assert!(db.soft_remove(&b));
+    assert_eq!(db.shortest_path_bidirectional(&a, &c, "near"), None);
+    assert_eq!(db.shortest_path_bidirectional(&a, &b, "near"), None);
+}
+
+/// records the walk, pruning at `prune` and stopping at `stop`
+#[derive(Default)]
+struct Record {
+    nodes: Vec<Key>,
+    edges: Vec<(Key, Key)>,
+    prune: Option<Key>,
+    stop: Option<Key>,
+}
+
+impl Visitor for Record {
+    fn visit_node(&mut self, key: &Key, _node: &Node) -> Control {
+        self.nodes.push(*key);
+        if Some(*key) == self.stop {
+            Control::Stop
+        } else if Some(*key) == self.prune {
+            Control::Prune
+        } else {
+            Control::Continue
+        }
+    }
+    fn visit_edge(&mut self, from: &Key, _kind: &str, to: &Key) -> Control {
+        self.edges.push((*from, *to));
+        Control::Continue
+    }
+}
+
+/// `a` has the children `b` and `d`, `b` has `c` and `c` points back at `a`
+fn tree() -> (Database, [Key; 4]) {
+    let mut db = Database::in_memory();
+    db.declare_kind("child", Directionality::Directed);
+    let [a, b, c, d] = [(); 4].map(|()| db.create(&()));
+    for (from, to) in [(a, b), (a, d), (b, c), (c, a)] {
+        assert!(db.connect(from, "child", to, "child"));
+    }
+    (db, [a, b, c, d])
+}
+
+#[test]
+fn walk_orders() {
+    let (db, [a, b, c, d]) = tree();
+    let position = |nodes: &[Key], key: Key| nodes.iter().position(|node| *node == key).unwrap();
+
+    let mut breadth = Record::default();
+    db.walk(&a, Order::BreadthFirst, &mut breadth);
+    assert_eq!(breadth.nodes.len(), 4);
+    assert_eq!(breadth.nodes[0], a);
+    assert_eq!(position(&breadth.nodes, c), 3);
+    // the edge back to `a` is still reported
+    assert_eq!(breadth.edges.len(), 4);
+    assert!(breadth.edges.contains(&(c, a)));
+
+    let mut depth = Record::default();
+    db.walk(&a, Order::DepthFirst, &mut depth);
+    assert_eq!(depth.nodes.len(), 4);
+    assert_eq!(position(&depth.nodes, c), position(&depth.nodes, b) + 1);
+    assert!(depth.nodes.contains(&d));
+}
+
+#[test]
+fn walk_prune_and_stop() {
+    let (db, [a, b, c, _d]) = tree();
+    let mut pruned = Record {
+        prune: Some(b),
+        ..Record::default()
+    };
+    db.walk(&a, Order::BreadthFirst, &mut pruned);
+    assert_eq!(pruned.nodes.len(), 3);
+    assert!(!pruned.nodes.contains(&c));
+
+    let mut stopped = Record {
+        stop: Some(a),
+        ..Record::default()
+    };
+    db.walk(&a, Order::DepthFirst, &mut stopped);
+    assert_eq!(stopped.nodes, [a]);
+    assert!(stopped.edges.is_empty());
+
+    let mut missing = Record::default();
+    db.walk(&Key::generate(), Order::BreadthFirst, &mut missing);
+    assert!(missing.nodes.is_empty());
+}
+
+#[test]
+fn nodes_at_distance() {
+    let mut db = Database::in_memory();
+    let [a, b, c, d, e] = [(); 5].map(|()| db.create(&()));
+    for (from, to) in [(a, b), (b, c), (a, d), (d, c), (c, e), (a, c)] {
+        assert!(db.connect(from, "friend", to, "friend"));
+    }
+    let ring = |distance| db.nodes_at_distance(&a, "friend", distance);
+    assert_eq!(ring(0), HashSet::from([a]));
+    // `c` is a friend of friends but a friend already
+    assert_eq!(ring(1), HashSet::from([b, c, d]));
+    assert_eq!(ring(2), HashSet::from([e]));
+    assert!(ring(3).is_empty());
+    assert!(db.nodes_at_distance(&e, "other", 1).is_empty());
+    assert!(
+        db.nodes_at_distance(&Key::generate(), "friend", 0)
+            .is_empty()
+    );
+}
+
+#[test]
+fn query_follows_kinds() {
+    let mut db = Database::in_memory();
+    let [a, b, c, x, y] = [(); 5].map(|()| db.create(&()));
+    assert!(db.connect(a, "friend", b, "friend"));
+    assert!(db.connect(a, "friend", c, "friend"));
+    for (from, to) in [(b, x), (c, x), (c, y)] {
+        assert!(db.connect(from, "likes", to, "liked by"));
+    }
+    let liked = db.query().starting_at(a).follow("friend").follow("likes");
+    assert_eq!(liked.collect(), HashSet::from([x, y]));
+    assert_eq!(
+        db.query()
+            .starting_at(x)
+            .starting_at(y)
+            .follow("liked by")
+            .collect(),
+        HashSet::from([b, c])
+    );
+    assert_eq!(db.query().starting_at(a).collect(), HashSet::from([a]));
+    assert!(
+        db.query()
+            .starting_at(a)
+            .follow("likes")
+            .collect()
+            .is_empty()
+    );
+    assert!(db.query().follow("friend").collect().is_empty());
+}
@@ -0,0 +1,343 @@
+use std::{
+    collections::HashMap,
+    hash::RandomState,
+    io,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use chrono::{TimeDelta, Utc};
+use grahh_db::{
+    CreateError, Database, Directionality, Key, LoadError, Node, SalvageError, SaveOnDrop,
+    SharedDatabase, StorageBackend,
+};
+use proptest::prelude::*;
+
+#[derive(Debug, Clone)]
+struct Graph {
+    values: Vec<Vec<u8>>,
+    edges: Vec<(usize, String, usize, String)>,
+}
+
+fn graph() -> impl Strategy<Value = Graph> {
+    prop::collection::vec(prop::collection::vec(any::<u8>(), 0..64), 0..32).prop_flat_map(
+        |values| {
+            let len = values.len().max(1);
+            let edge = (0..len, "[a-z]{1,4}", 0..len, "[a-z]{1,4}");
+            (Just(values), prop::collection::vec(edge, 0..64))
+                .prop_map(|(values, edges)| Graph { values, edges })
+        },
+    )
+}
+
+/// every snapshot saved to it, shared with the test
+#[derive(Debug, Clone, Default)]
+struct Saves(Arc<Mutex<Vec<Vec<u8>>>>);
+
+impl StorageBackend for Saves {
+    fn save(&self, bytes: &[u8]) -> io::Result<()> {
+        self.0.lock().unwrap().push(bytes.to_vec());
+        Ok(())
+    }
+    fn load(&self) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.0.lock().unwrap().last().cloned())
+    }
+}
+
+impl Saves {
+    fn count(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+fn nodes(db: &Database) -> HashMap<Key, &Node> {
+    db.iter().map(|(key, node)| (*key, node)).collect()
+}
+
+proptest! {
+    #[test]
+    fn save_load_round_trip(graph in graph()) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db.grahh");
+
+        let mut db = Database::load(path.clone());
+        let keys: Vec<Key> = graph.values.iter().map(|value| db.create(value)).collect();
+        for (first, first_kind, second, second_kind) in graph.edges {
+            if first != second {
+                db.connect(keys[first], first_kind, keys[second], second_kind);
+            }
+        }
+        db.save();
+
+        let loaded = Database::load(path);
+        prop_assert_eq!(nodes(&db), nodes(&loaded));
+    }
+}
+
+#[test]
+fn load_empty_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    std::fs::File::create(&path).unwrap();
+
+    let db = Database::load(path);
+    assert_eq!(db.iter().count(), 0);
+}
+
+#[test]
+fn load_salvage() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+
+    let mut db = Database::load(path.clone());
+    db.set_backups(1);
+    let first = db.create(&1u64);
+    let second = db.create(&2u64);
+    db.connect(first, "friend", second, "friend");
+    db.save();
+    db.create(&3u64);
+    db.save();
+    drop(db);
+    std::fs::write(&path, b"not a database").unwrap();
+
+    let (db, lost) = Database::load_salvage(path.clone());
+    assert_eq!(db.iter().count(), 2);
+    assert_eq!(db.select(&first, "friend").len(), 1);
+    assert!(matches!(lost[..], [SalvageError::File { .. }]));
+
+    db.save_indexed(&path).unwrap();
+    let len = std::fs::metadata(&path).unwrap().len();
+    std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_len(len - 1)
+        .unwrap();
+    let (db, lost) = Database::load_salvage(path);
+    let [SalvageError::Node { key, .. }] = lost[..] else {
+        panic!("expected one damaged node, lost {lost:?}");
+    };
+    assert_eq!(db.iter().count(), 1);
+    assert!(db.get(&key).is_none());
+    assert!(
+        db.iter()
+            .all(|(_key, node)| node.connections().count() == 0)
+    );
+}
+
+#[test]
+fn save_on_drop() {
+    let saves = Saves::default();
+    let mut db = Database::load_backend(saves.clone());
+    db.set_save_on_drop(SaveOnDrop::Panic);
+    let first = db.create(&1u64);
+    let clone = db.clone();
+    db.save();
+    drop(clone);
+    assert_eq!(saves.count(), 1);
+    drop(db);
+    assert_eq!(saves.count(), 1);
+
+    let mut db = Database::load_backend(saves.clone());
+    db.set_save_on_drop(SaveOnDrop::Panic);
+    let shared = SharedDatabase::new(db);
+    let old = shared.snapshot();
+    let second = shared.write(|db| db.create(&2u64));
+    assert!(old.get(&first).is_some());
+    assert!(old.get(&second).is_none());
+    assert!(shared.snapshot().get(&second).is_some());
+    drop(old);
+    assert_eq!(saves.count(), 1);
+    drop(shared);
+    assert_eq!(saves.count(), 2);
+    let db = Database::load_backend(saves);
+    assert!(db.get(&first).is_some());
+    assert!(db.get(&second).is_some());
+}
+
+#[test]
+fn snapshots_across_threads() {
+    let shared = SharedDatabase::new(Database::in_memory());
+    let first = shared.write(|db| db.create(&1u64));
+    let old = shared.snapshot();
+    let keys: Vec<Key> = thread::scope(|scope| {
+        let shared = &shared;
+        let writers: Vec<_> = (2..6u64)
+            .map(|value| scope.spawn(move || shared.write(|db| db.create(&value))))
+            .collect();
+        writers
+            .into_iter()
+            .map(|writer| writer.join().unwrap())
+            .collect()
+    });
+    assert_eq!(old.len(), 1);
+    assert!(old.get(&first).is_some());
+    // one writer at a time so none of them lost another's node
+    let new = shared.snapshot();
+    assert_eq!(new.len(), 5);
+    assert!(keys.iter().all(|key| new.get(key).is_some()));
+}
+
+#[test]
+fn builder_options() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut db = Database::builder()
+        .declare_kind("follows", Directionality::Directed)
+        .max_nodes(3)
+        .max_value_bytes(64)
+        .edge_timestamps(true)
+        .unique_targets(true)
+        .hasher::<RandomState>()
+        .open(path.clone());
+    assert_eq!(db.kind("follows"), Some(Directionality::Directed));
+    let [a, b] = [1u8, 2].map(|value| db.create(&value));
+    assert!(db.connect(a, "follows", b, "follows"));
+    assert!(db.edge_created_at(&a, "follows", &b).is_some());
+    assert!(!db.connect(a, "friend", b, "friend"));
+    assert!(matches!(
+        db.try_create(&vec![0u8; 100]),
+        Err(CreateError::Invalid(_))
+    ));
+    db.create(&3u8);
+    assert!(matches!(db.try_create(&4u8), Err(CreateError::Full(_))));
+    db.save();
+
+    let db = Database::builder()
+        .declare_kind("follows", Directionality::Directed)
+        .open(path);
+    assert_eq!(db.len(), 3);
+    assert!(db.get(&b).unwrap().get_incoming("follows").contains(&a));
+    assert!(db.edge_created_at(&a, "follows", &b).is_some());
+    assert!(Database::builder().capacity(10).build().is_empty());
+}
+
+#[test]
+fn edge_timestamps() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut db = Database::load(path.clone());
+    let [a, b, c] = [(); 3].map(|()| db.create(&()));
+    let before = Utc::now();
+    db.set_edge_timestamps(true);
+    assert!(db.connect(a, "friend", b, "friend"));
+    db.set_edge_timestamps(false);
+    assert!(db.connect(a, "friend", c, "friend"));
+
+    let at = db.edge_created_at(&a, "friend", &b).unwrap();
+    assert!(at >= before && at <= Utc::now());
+    assert_eq!(db.edge_created_at(&b, "friend", &a), Some(at));
+    assert_eq!(db.edge_created_at(&a, "friend", &c), None);
+    assert_eq!(db.edge_created_at(&a, "other", &b), None);
+    let mut since: Vec<(Key, &str, Key)> = db.edges_since(before).collect();
+    since.sort_unstable();
+    assert_eq!(since, [(a, "friend", b), (b, "friend", a)]);
+    assert_eq!(db.edges_since(at + TimeDelta::seconds(1)).count(), 0);
+
+    db.save();
+    let db = Database::load(path);
+    assert_eq!(db.edge_created_at(&a, "friend", &b), Some(at));
+    assert_eq!(db.edge_created_at(&a, "friend", &c), None);
+}
+
+/// how the first release laid out its database file, without a header
+mod first_release {
+    use std::collections::{HashMap, HashSet};
+
+    use grahh_db::Key;
+
+    #[derive(rkyv::Archive, rkyv::Serialize)]
+    pub struct Value(pub Vec<u8>);
+
+    #[derive(rkyv::Archive, rkyv::Serialize)]
+    pub struct Node {
+        pub value: Value,
+        pub connections: HashMap<String, HashSet<Key>>,
+    }
+}
+
+#[test]
+fn load_first_release_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let (a, b) = (Key::generate(), Key::generate());
+    let node = |value: u32, other: Key| first_release::Node {
+        value: first_release::Value(rkyv::to_bytes::<rancor::Error>(&value).unwrap().into_vec()),
+        connections: HashMap::from([("friend".to_owned(), [other].into())]),
+    };
+    let nodes = HashMap::from([(a, node(1, b)), (b, node(2, a))]);
+    std::fs::write(&path, rkyv::to_bytes::<rancor::Error>(&nodes).unwrap()).unwrap();
+    Database::verify(&path).unwrap();
+
+    let db = Database::load(path);
+    let value = db.get(&a).unwrap().value();
+    assert_eq!(
+        value
+            .deserialize::<rkyv::Archived<u32>>()
+            .unwrap()
+            .to_native(),
+        1
+    );
+    assert!(db.select(&a, "friend").contains(&b));
+    assert!(db.select(&b, "friend").contains(&a));
+}
+
+#[test]
+fn load_newer_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut bytes = b"grahhdb\0".to_vec();
+    bytes.extend(99u32.to_le_bytes());
+    bytes.extend([0; 4]);
+    std::fs::write(&path, bytes).unwrap();
+    assert!(matches!(
+        Database::try_load(path),
+        Err(LoadError::SnapshotVersion(99))
+    ));
+}
+
+#[test]
+fn durable_save() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut db = Database::load(path.clone());
+    db.set_durable(true);
+    db.set_blob_threshold(Some(0));
+    let key = db.create(&"stored out of line".to_owned());
+    db.save();
+    db.save_indexed(&dir.path().join("db.grahhidx")).unwrap();
+    let db = Database::load(path);
+    assert!(db.get(&key).is_some());
+}
+
+#[test]
+fn blob_name_taken_by_other_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut db = Database::load(path.clone());
+    db.set_blob_threshold(Some(0));
+    let value = "stored out of line".to_owned();
+    db.create(&value);
+    db.save();
+    // another value that happens to hash the same, left where the next one would go
+    let blobs = dir.path().join("db.grahh.blobs");
+    let blob = std::fs::read_dir(&blobs)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let other = vec![0; std::fs::metadata(&blob).unwrap().len() as usize];
+    std::fs::write(&blob, other).unwrap();
+
+    let key = db.create(&value);
+    db.save();
+    let db = Database::load(path);
+    let loaded = db.get(&key).unwrap().value();
+    assert_eq!(
+        loaded
+            .deserialize::<rkyv::string::ArchivedString>()
+            .unwrap(),
+        value.as_str()
+    );
+}
@@ -0,0 +1,75 @@
+use grahh_db::{Database, Directionality, Key, RemapError};
+
+fn key(key: u64) -> Key {
+    Key::parse(&key.to_string()).unwrap()
+}
+
+#[test]
+fn remap_moves_every_connection() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let old = db.create(&1u8);
+    let [friend, follower, weak, removed] = [(); 4].map(|()| db.create(&0u8));
+    assert!(db.connect(old, "friend", friend, "friend"));
+    assert!(db.connect(follower, "follows", old, "follows"));
+    assert!(db.connect_weak(weak, "saw", old));
+    assert!(db.connect(removed, "friend", old, "friend"));
+    assert!(db.soft_remove(&removed));
+
+    let new = key(u64::MAX);
+    db.remap_key(old, new).unwrap();
+    assert!(db.get(&old).is_none());
+    assert_eq!(
+        *db.get(&new).unwrap().value().deserialize::<u8>().unwrap(),
+        1
+    );
+    assert!(db.select(&friend, "friend").contains(&new));
+    assert!(db.select(&new, "friend").contains(&friend));
+    assert!(db.select(&follower, "follows").contains(&new));
+    assert!(
+        db.get(&new)
+            .unwrap()
+            .get_incoming("follows")
+            .contains(&follower)
+    );
+    assert_eq!(db.select_weak(&weak, "saw").collect::<Vec<_>>(), [new]);
+    // the soft removed node comes back connected to the new key
+    assert!(db.restore(&removed));
+    assert!(db.select(&removed, "friend").contains(&new));
+    assert!(db.select(&new, "friend").contains(&removed));
+    assert!(!db.select(&new, "friend").contains(&old));
+}
+
+#[test]
+fn remap_errors() {
+    let mut db = Database::in_memory();
+    let a = db.create(&1u8);
+    let b = db.create(&2u8);
+    let removed = db.create(&3u8);
+    assert!(db.soft_remove(&removed));
+    assert!(matches!(
+        db.remap_key(key(u64::MAX), a),
+        Err(RemapError::Missing(_))
+    ));
+    assert!(matches!(
+        db.remap_key(removed, key(u64::MAX)),
+        Err(RemapError::Missing(_))
+    ));
+    assert!(matches!(db.remap_key(a, b), Err(RemapError::Exists(_))));
+    assert!(matches!(
+        db.remap_key(a, removed),
+        Err(RemapError::Exists(_))
+    ));
+    assert!(db.remap_key(a, a).is_ok());
+    assert!(db.get(&a).is_some());
+}
+
+#[test]
+fn generated_keys_skip_remapped() {
+    let mut db = Database::in_memory();
+    let a = db.create(&1u8);
+    let far = Key::generate();
+    db.remap_key(a, far).unwrap();
+    assert_ne!(db.create(&2u8), far);
+    assert_eq!(db.len(), 2);
+}
@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+
+use grahh_db::{Database, Directionality, Key};
+
+#[test]
+fn remove_cascade_with_cycle() {
+    let mut db = Database::in_memory();
+    db.declare_kind("contains", Directionality::Directed);
+    let [a, b, c, outside] = [(); 4].map(|()| db.create(&()));
+    for (from, to) in [(a, b), (b, c), (c, a), (outside, a)] {
+        assert!(db.connect(from, "contains", to, "contains"));
+    }
+    assert!(db.connect(outside, "friend", c, "friend"));
+
+    let removed = db.remove_cascade(a, "contains");
+    assert_eq!(removed.len(), 3);
+    assert_eq!(
+        removed.into_iter().collect::<HashSet<_>>(),
+        HashSet::from([a, b, c])
+    );
+    assert_eq!(db.len(), 1);
+    let outside = db.get(&outside).unwrap();
+    assert!(outside.is_isolated());
+    assert!(outside.get_connections("contains").is_empty());
+    assert!(outside.get_connections("friend").is_empty());
+    assert_eq!(db.edges(false).count(), 0);
+    assert!(db.remove_cascade(a, "contains").is_empty());
+    assert!(db.remove_cascade(Key::generate(), "contains").is_empty());
+}
+
+#[test]
+fn remove_many_in_order() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let [hub, a, b, c, d] = [0u8, 1, 2, 3, 4].map(|value| db.create(&value));
+    assert_eq!(db.connect_star(hub, "friend", [a, b, c]), 3);
+    assert!(db.connect(a, "friend", b, "friend"));
+    assert!(db.connect(a, "follows", d, "follows"));
+
+    let values = db.remove_many([c, Key::generate(), a, c]);
+    let values: Vec<Option<u8>> = values
+        .iter()
+        .map(|value| {
+            value
+                .as_ref()
+                .map(|value| *value.deserialize::<u8>().unwrap())
+        })
+        .collect();
+    assert_eq!(values, [Some(3), None, Some(1), None]);
+    assert_eq!(db.len(), 3);
+    assert_eq!(db.select(&hub, "friend").iter().collect::<Vec<_>>(), [&b]);
+    assert_eq!(db.select(&b, "friend").iter().collect::<Vec<_>>(), [&hub]);
+    assert!(db.get(&d).unwrap().get_incoming("follows").is_empty());
+    assert!(db.remove_many([]).is_empty());
+}
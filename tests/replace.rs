@@ -0,0 +1,55 @@
+use grahh_db::{Database, Directionality, Key, Node};
+
+fn value(node: &Node) -> u8 {
+    *node.value().deserialize::<u8>().unwrap()
+}
+
+#[test]
+fn replace_rewires_connections() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let key = db.create(&1u8);
+    let [old_friend, friend, followed, removed] = [(); 4].map(|()| db.create(&0u8));
+    assert!(db.connect(key, "friend", old_friend, "friend"));
+    assert!(db.soft_remove(&removed));
+
+    let mut node = Node::new(&2u8);
+    node.connect("friend".to_owned(), friend);
+    node.connect("follows".to_owned(), followed);
+    node.connect("friend".to_owned(), key);
+    node.connect("friend".to_owned(), removed);
+    node.connect("friend".to_owned(), Key::generate());
+    let old = db.replace_node(key, node).unwrap();
+    assert_eq!(value(&old), 1);
+
+    let new = db.get(&key).unwrap();
+    assert_eq!(value(new), 2);
+    assert_eq!(new.get_connections("friend").len(), 1);
+    assert!(new.get_connections("friend").contains(&friend));
+    assert!(db.select(&old_friend, "friend").is_empty());
+    assert!(db.select(&friend, "friend").contains(&key));
+    assert!(db.select(&followed, "follows").is_empty());
+    assert!(
+        db.get(&followed)
+            .unwrap()
+            .get_incoming("follows")
+            .contains(&key)
+    );
+    assert!(db.restore(&removed));
+    assert!(db.select(&removed, "friend").is_empty());
+}
+
+#[test]
+fn replace_missing_and_soft_removed() {
+    let mut db = Database::in_memory();
+    let key = Key::generate();
+    assert!(db.replace_node(key, Node::new(&1u8)).is_none());
+    assert_eq!(value(db.get(&key).unwrap()), 1);
+
+    assert!(db.soft_remove(&key));
+    let old = db.replace_node(key, Node::new(&2u8)).unwrap();
+    assert!(old.is_deleted());
+    assert!(!db.get(&key).unwrap().is_deleted());
+    assert_eq!(value(db.get(&key).unwrap()), 2);
+    assert_eq!(db.len(), 1);
+}
@@ -0,0 +1,112 @@
+use grahh_db::{CreateError, Database, Directionality, Key};
+
+fn value(db: &Database, key: &Key) -> u8 {
+    *db.get(key).unwrap().value().deserialize::<u8>().unwrap()
+}
+
+#[test]
+fn entry_of_soft_removed() {
+    let mut db = Database::in_memory();
+    let key = db.create(&1u8);
+    assert!(db.soft_remove(&key));
+    assert!(matches!(
+        db.entry(key).try_or_insert(&2u8),
+        Err(CreateError::Exists(_))
+    ));
+    let _ = db.entry(key).and_update(&3u8);
+    assert!(db.restore(&key));
+    assert_eq!(value(&db, &key), 1);
+}
+
+#[test]
+fn map_values_skips_soft_removed() {
+    let mut db = Database::in_memory();
+    let live = db.create(&1u8);
+    let removed = db.create(&2u8);
+    assert!(db.soft_remove(&removed));
+    assert!(db.map_values(|value: u8| value + 10).is_empty());
+    assert!(db.restore(&removed));
+    assert_eq!(value(&db, &live), 11);
+    assert_eq!(value(&db, &removed), 2);
+}
+
+#[test]
+fn rebuild_indexes_with_soft_removed() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let a = db.create(&1u8);
+    let removed = db.create(&2u8);
+    let b = db.create(&3u8);
+    assert!(db.connect(a, "follows", removed, "follows"));
+    assert!(db.connect(removed, "follows", b, "follows"));
+    assert!(db.soft_remove(&removed));
+    db.rebuild_indexes();
+    assert!(db.get(&b).unwrap().get_incoming("follows").is_empty());
+
+    // the removed node kept who follows it for when it comes back
+    assert!(db.restore(&removed));
+    let restored = db.get(&removed).unwrap();
+    assert!(restored.get_incoming("follows").contains(&a));
+    assert!(
+        db.get(&b)
+            .unwrap()
+            .get_incoming("follows")
+            .contains(&removed)
+    );
+}
+
+#[test]
+fn restore_after_neighbor_soft_removed() {
+    let mut db = Database::in_memory();
+    db.declare_kind("follows", Directionality::Directed);
+    let a = db.create(&1u8);
+    let b = db.create(&2u8);
+    assert!(db.connect(a, "friend", b, "friend"));
+    assert!(db.connect(a, "follows", b, "follows"));
+    assert!(db.soft_remove(&a));
+    assert!(db.soft_remove(&b));
+
+    // coming back first means waiting for the other one
+    assert!(db.restore(&a));
+    assert!(db.select(&a, "friend").is_empty());
+    assert!(db.select(&a, "follows").is_empty());
+    assert!(db.restore(&b));
+    assert!(db.select(&a, "friend").contains(&b));
+    assert!(db.select(&b, "friend").contains(&a));
+    assert!(db.select(&a, "follows").contains(&b));
+    assert!(db.get(&b).unwrap().get_incoming("follows").contains(&a));
+
+    // the other way around ends up the same
+    assert!(db.soft_remove(&a));
+    assert!(db.soft_remove(&b));
+    assert!(db.restore(&b));
+    assert!(db.get(&b).unwrap().get_incoming("follows").is_empty());
+    assert!(db.restore(&a));
+    assert!(db.select(&b, "friend").contains(&a));
+    assert!(db.get(&b).unwrap().get_incoming("follows").contains(&a));
+}
+
+#[test]
+fn restore_after_neighbor_removed() {
+    let mut db = Database::in_memory();
+    let a = db.create(&1u8);
+    let b = db.create(&2u8);
+    let c = db.create(&3u8);
+    assert!(db.connect(a, "friend", b, "friend"));
+    assert!(db.connect(a, "friend", c, "friend"));
+    assert!(db.soft_remove(&a));
+    assert!(db.soft_remove(&b));
+    assert_eq!(db.purge_deleted(), 2);
+    assert!(!db.restore(&a));
+
+    let a = db.create(&1u8);
+    let b = db.create(&2u8);
+    assert!(db.connect(a, "friend", b, "friend"));
+    assert!(db.connect(a, "friend", c, "friend"));
+    assert!(db.soft_remove(&a));
+    assert!(db.remove(b).is_some());
+    assert!(db.restore(&a));
+    assert_eq!(db.select(&a, "friend").len(), 1);
+    assert!(db.select(&a, "friend").contains(&c));
+    assert!(db.select(&c, "friend").contains(&a));
+}
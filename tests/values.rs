@@ -0,0 +1,81 @@
+#[cfg(feature = "zstd")]
+use grahh_db::Database;
+use grahh_db::Value;
+use rkyv::{Archived, rancor, string::ArchivedString};
+
+/// two archives back to back, the second starting at 16 so it's aligned for anything
+fn composite() -> Value {
+    let mut bytes = rkyv::to_bytes::<rancor::Error>(&7u32).unwrap().into_vec();
+    bytes.resize(16, 0);
+    let name = rkyv::to_bytes::<rancor::Error>(&"composite".to_owned()).unwrap();
+    bytes.extend_from_slice(&name);
+    Value::from_bytes(bytes)
+}
+
+#[test]
+fn deserialize_slice() {
+    let value = composite();
+    let len = value.len();
+    let first = value.deserialize_slice::<Archived<u32>>(0..4).unwrap();
+    assert_eq!(first.to_native(), 7);
+    let name = value.deserialize_slice::<ArchivedString>(16..len).unwrap();
+    assert_eq!(name.as_str(), "composite");
+}
+
+#[test]
+fn deserialize_slice_out_of_range() {
+    let value = composite();
+    let len = value.len();
+    assert!(
+        value
+            .deserialize_slice::<ArchivedString>(16..len + 1)
+            .is_none()
+    );
+    assert!(
+        value
+            .deserialize_slice::<ArchivedString>(len + 8..len + 16)
+            .is_none()
+    );
+    let (start, end) = (4, 0);
+    assert!(
+        value
+            .deserialize_slice::<Archived<u32>>(start..end)
+            .is_none()
+    );
+    assert!(
+        value
+            .deserialize_slice::<Archived<u32>>(usize::MAX..usize::MAX)
+            .is_none()
+    );
+    // misaligned for a u32, too short for a string and a 7 that isn't a bool
+    assert!(value.deserialize_slice::<Archived<u32>>(1..5).is_none());
+    assert!(value.deserialize_slice::<ArchivedString>(16..20).is_none());
+    assert!(value.deserialize_slice::<bool>(0..1).is_none());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn values_over_the_threshold_are_compressed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.grahh");
+    let mut db = Database::load(path.clone());
+    db.set_value_compression(Some(64), 3);
+    let text = "compressible ".repeat(100);
+    let large = db.create(&text);
+    let small = db.create(&"small".to_owned());
+
+    let check = |db: &Database| {
+        let value = db.get_value(&large).unwrap();
+        assert!(value.len() < value.as_bytes().unwrap().len());
+        assert_eq!(
+            value.deserialize::<ArchivedString>().unwrap(),
+            text.as_str()
+        );
+        let value = db.get_value(&small).unwrap();
+        assert_eq!(value.len(), value.as_bytes().unwrap().len());
+        assert_eq!(value.deserialize::<ArchivedString>().unwrap(), "small");
+    };
+    check(&db);
+    db.save();
+    check(&Database::load(path));
+}